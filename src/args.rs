@@ -0,0 +1,194 @@
+use crate::logging;
+use client_rs::{
+  config::{
+    self,
+    ConfigOverrides
+  },
+  input::switch::SwitchPad
+};
+
+use clap::{
+  Parser,
+  ValueEnum
+};
+use std::{
+  collections::HashMap,
+  path::PathBuf,
+  str::FromStr
+};
+
+// Represents the libraries that gamepad inputs can be read through.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Backend {
+  Sdl,
+  Gilrs,
+  #[clap(name = "multiinput")]
+  MultiInput
+}
+
+/**
+ * Represents the arguments the client was run with.
+ *
+ * Settings given here override the ones in the config, but aren't saved to it
+ * unless --save is also given.
+ */
+#[derive(Parser)]
+#[clap(
+  version, about = "A client for the sys-hidplus Switch sysmodule.",
+  long_about = None
+)]
+pub struct Args {
+  #[clap(
+    long, value_name = "IP",
+    help = "The IP of the Switch to send inputs to"
+  )]
+  server_ip: Option<String>,
+
+  #[clap(
+    long, value_name = "PORT",
+    help = "The port the Switch is listening on"
+  )]
+  server_port: Option<u16>,
+
+  #[clap(
+    long, value_name = "PATH",
+    help = "The config file to load and save settings with [default: \
+    ./config.toml if it exists, otherwise one in your config directory]"
+  )]
+  config: Option<PathBuf>,
+
+  #[clap(
+    long, value_enum, default_value = "sdl",
+    help = "The library to read gamepad inputs through"
+  )]
+  backend: Backend,
+
+  #[clap(
+    long = "switch-pad", value_name = "SLOT=PAD",
+    value_parser = parse_slot_value::<SwitchPad>,
+    help = "Sets the Switch controller type of a slot, like 0=JoyConLSide"
+  )]
+  switch_pads: Vec<(usize, SwitchPad)>,
+
+  #[clap(
+    long = "input-delay", value_name = "SLOT=FRAMES",
+    value_parser = parse_slot_value::<u8>,
+    help = "Sets the input delay of a slot, like 1=3"
+  )]
+  input_delays: Vec<(usize, u8)>,
+
+  #[clap(long, help = "Saves the settings above to the config")]
+  save: bool,
+
+  #[clap(long, help = "Starts the client as soon as it's loaded")]
+  autostart: bool,
+
+  #[clap(
+    long, conflicts_with = "headless",
+    help = "Uses a full-screen interface instead of the command line"
+  )]
+  tui: bool,
+
+  #[clap(long, help = "Never reads commands from stdin")]
+  headless: bool,
+
+  #[clap(
+    long, value_name = "ADDRESS",
+    help = "Accepts JSON-RPC calls on a Unix socket path, or on a localhost \
+    address like 127.0.0.1:8001"
+  )]
+  rpc: Option<String>,
+
+  #[clap(
+    long, value_name = "ADDRESS",
+    help = "Serves a REST API and event stream on a localhost address, like \
+    127.0.0.1:8002"
+  )]
+  http: Option<String>,
+
+  #[clap(
+    long, value_name = "SPEC",
+    help = "Which levels to log, either everywhere or per module, like \
+    'info, client_rs::input=trace' [default: $RUST_LOG, otherwise info]"
+  )]
+  log_level: Option<String>,
+
+  #[clap(
+    long, value_name = "PATH",
+    help = "The folder to keep log files in [default: a logs folder next to \
+    the config]"
+  )]
+  log_dir: Option<PathBuf>
+}
+
+impl Args {
+  // Returns the path of the config, falling back to the default one.
+  pub fn config_path(&self) -> PathBuf {
+    return match &self.config {
+      Some(path) => path.to_path_buf(),
+      None => config::default_config_path()
+    }
+  }
+
+  pub fn get_backend(&self) -> &Backend {
+    return &self.backend;
+  }
+
+  pub fn get_save(&self) -> &bool {
+    return &self.save;
+  }
+
+  pub fn get_autostart(&self) -> &bool {
+    return &self.autostart;
+  }
+
+  pub fn get_tui(&self) -> &bool {
+    return &self.tui;
+  }
+
+  pub fn get_headless(&self) -> &bool {
+    return &self.headless;
+  }
+
+  pub fn get_rpc(&self) -> &Option<String> {
+    return &self.rpc;
+  }
+
+  pub fn get_http(&self) -> &Option<String> {
+    return &self.http;
+  }
+
+  pub fn get_log_level(&self) -> &Option<String> {
+    return &self.log_level;
+  }
+
+  // Returns the folder to log to, falling back to the one next to the config.
+  pub fn log_dir(&self) -> PathBuf {
+    return match &self.log_dir {
+      Some(path) => path.to_path_buf(),
+      None => logging::default_log_dir(&self.config_path())
+    }
+  }
+
+  // Returns the config settings these arguments override.
+  pub fn overrides(&self) -> ConfigOverrides {
+    return ConfigOverrides::new(
+      self.server_ip.clone(),
+      self.server_port,
+      self.switch_pads.iter().cloned().collect::<HashMap<usize, SwitchPad>>(),
+      self.input_delays.iter().cloned().collect::<HashMap<usize, u8>>()
+    );
+  }
+}
+
+// Parses a per-slot argument, which is given as 'slot=value'.
+fn parse_slot_value<T: FromStr>(s: &str) -> Result<(usize, T), String> {
+  return match s.split_once('=') {
+    Some((i, value)) => match (usize::from_str(i), T::from_str(value)) {
+      (Ok(i), Ok(value)) => Ok((i, value)),
+      (Err(_), _) => Err(format!("'{}' isn't a valid slot index.", i)),
+      (_, Err(_)) => Err(format!("'{}' isn't a valid value.", value))
+    },
+    None => Err(format!("'{}' should be given as 'slot=value'.", s))
+  }
+}
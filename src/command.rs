@@ -0,0 +1,1096 @@
+use crate::config;
+
+use std::{
+  fmt,
+  str::FromStr
+};
+
+/**
+ * Represents a command that can be given to the controller, along with its
+ * already-parsed arguments.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+  Help(Option<String>),
+  Restart,
+  Start,
+  Stop,
+  Exit,
+  ReleaseAll,
+  Status(bool),
+  Stats,
+  Devices,
+  Assign(usize, usize),
+  Kick(usize),
+  Swap(usize, usize),
+  Lock(usize),
+  Unlock(usize),
+  ShowSlot(usize),
+  SetSlot(usize, String, String),
+  Profile(ProfileAction),
+  Reload,
+  Source(String),
+  SetServerIp(String),
+  SetAutoAssign(bool),
+  SetPreferredSlot(usize, Option<usize>)
+}
+
+// Represents the different things that can be done with profiles.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProfileAction {
+  Save(String),
+  Load(String),
+  List,
+  Delete(String)
+}
+
+/**
+ * Represents everything that can be wrong with a command before it's even run.
+ * Errors about a specific command include its usage when displayed.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum CommandError {
+  UnclosedQuote(char),
+  UnknownCommand(String),
+  MissingArgument {
+    command: &'static str,
+    argument: &'static str
+  },
+  InvalidArgument {
+    command: &'static str,
+    argument: &'static str,
+    value: String,
+    expected: &'static str
+  },
+  UnexpectedArgument {
+    command: &'static str,
+    value: String
+  }
+}
+
+impl fmt::Display for CommandError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    return match self {
+      CommandError::UnclosedQuote(quote) => write!(
+        f, "The command has a {} quote that was never closed.", quote
+      ),
+      CommandError::UnknownCommand(name) => write!(
+        f,
+        "'{}' is not a valid command. Type 'help' for a list of all \
+        available commands.",
+        name
+      ),
+      CommandError::MissingArgument { command, argument } => write!(
+        f, "'{}' is missing {}.\n{}", command, argument, usage_hint(command)
+      ),
+      CommandError::InvalidArgument { command, argument, value, expected } =>
+        write!(
+          f, "'{}' isn't a valid {}, it should be {}.\n{}",
+          value, argument, expected, usage_hint(command)
+        ),
+      CommandError::UnexpectedArgument { command, value } => write!(
+        f, "'{}' doesn't take '{}'.\n{}", command, value, usage_hint(command)
+      )
+    }
+  }
+}
+
+/**
+ * Represents the usage info of a command. Every command has exactly one of
+ * these in COMMANDS, which is where the help text, the errors, the list of
+ * command names, and how the command is parsed all come from.
+ */
+pub struct CommandSpec {
+  name: &'static str,
+  aliases: &'static [&'static str],
+  synopsis: &'static str,
+  usage: &'static [&'static str],
+  // The most arguments the command takes, or None if there's no limit.
+  max_args: Option<usize>,
+  parse: fn(&mut Arguments) -> Result<Command, CommandError>,
+  summary: &'static str,
+  details: &'static str
+}
+
+impl CommandSpec {
+  pub fn get_name(&self) -> &'static str {
+    return self.name;
+  }
+
+  pub fn get_aliases(&self) -> &'static [&'static str] {
+    return self.aliases;
+  }
+
+  pub fn get_synopsis(&self) -> &'static str {
+    return self.synopsis;
+  }
+
+  pub fn get_usage(&self) -> &'static [&'static str] {
+    return self.usage;
+  }
+
+  pub fn get_summary(&self) -> &'static str {
+    return self.summary;
+  }
+
+  pub fn get_details(&self) -> &'static str {
+    return self.details;
+  }
+
+  // Returns whether this command goes by a given name.
+  fn is_called(&self, name: &str) -> bool {
+    return self.name == name || self.aliases.contains(&name);
+  }
+
+  // Returns every way this command can be used, one per line.
+  fn usage_lines(&self) -> String {
+    return format!("Usage: {}", self.usage.join("\n       "));
+  }
+
+  // Parses the arguments given after the command's name.
+  fn parse_args(&self, tokens: &[String]) -> Result<Command, CommandError> {
+    if let Some(max_args) = self.max_args {
+      if let Some(token) = tokens.get(max_args) {
+        return Err(
+          CommandError::UnexpectedArgument {
+            command: self.name,
+            value: token.to_string()
+          }
+        );
+      }
+    }
+    let mut args: Arguments = Arguments::new(self.name, tokens);
+    let command: Command = match (self.parse)(&mut args) {
+      Ok(command) => command,
+      Err(e) => return Err(e)
+    };
+    return match args.finish() {
+      Ok(_) => Ok(command),
+      Err(e) => Err(e)
+    }
+  }
+}
+
+// Every command, in the order they're listed by 'help'.
+pub const COMMANDS: [CommandSpec; 21] = [
+  CommandSpec {
+    name: "help",
+    aliases: &[],
+    synopsis: "help (command)",
+    usage: &["help (command)"],
+    max_args: Some(1),
+    parse: parse_help,
+    summary: "Provides a list of available commands. You can specify a \
+      command after 'help' to view its full usage info.",
+    details: "(command) can be the name of any command.\n\n\
+      Example, if you want to see the usage of 'set_server_ip':\n\n\
+      help set_server_ip"
+  },
+  CommandSpec {
+    name: "restart",
+    aliases: &[],
+    synopsis: "restart",
+    usage: &["restart"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Restart),
+    summary: "Restarts the client. The client must be running.",
+    details: ""
+  },
+  CommandSpec {
+    name: "start",
+    aliases: &[],
+    synopsis: "start",
+    usage: &["start"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Start),
+    summary: "Starts the client.",
+    details: "If the client is still cleaning up gamepads after being \
+      stopped, it starts once that's done."
+  },
+  CommandSpec {
+    name: "stop",
+    aliases: &[],
+    synopsis: "stop",
+    usage: &["stop"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Stop),
+    summary: "Stops the client and disconnects all connected gamepads.",
+    details: "Gamepads are disconnected over the next few seconds, which can \
+      be changed with cleanup_ms in the config. If 'start' was run while that \
+      was happening, this keeps the client from starting afterwards."
+  },
+  CommandSpec {
+    name: "exit",
+    aliases: &[],
+    synopsis: "exit",
+    usage: &["exit"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Exit),
+    summary: "Same as 'stop', but totally exits the application.",
+    details: "If the client is cleaning up gamepads, it only exits once \
+      that's done."
+  },
+  CommandSpec {
+    name: "set_server_ip",
+    aliases: &[],
+    synopsis: "set_server_ip 'server_ip'",
+    usage: &["set_server_ip 'server_ip'"],
+    max_args: Some(1),
+    parse: parse_set_server_ip,
+    summary: "Sets the server IP to whatever 'server_ip' is.",
+    details: "Example, if your Switch's IP is 192.168.1.199:\n\n\
+      set_server_ip 192.168.1.199"
+  },
+  CommandSpec {
+    name: "slot",
+    aliases: &[],
+    synopsis: "slot 'i' (set 'key' 'value')",
+    usage: &["slot 'i'", "slot 'i' set 'key' 'value'"],
+    max_args: None,
+    parse: parse_slot,
+    summary: "Shows the settings of slot ('i' + 1), or changes one of them.",
+    details: "'i' must be either 0 or a positive integer. It also represents \
+      the target index: slot numbers are always equal to 'i' + 1.\n\n\
+      With just 'i', every setting of the slot is shown. Otherwise, 'key' \
+      must be one of the following:\n\n\
+      - switch_pad is the Switch controller type, and must be one of: \
+      Disconnected, ProController, JoyConLSide, or JoyConRSide. It applies \
+      once a gamepad is connected to the slot again.\n\n\
+      - input_delay is how many frames the slot's inputs are delayed by, from \
+      0 to 255.\n\n\
+      - hold_timeout is how many seconds the slot stays connected to the \
+      Switch (with neutral inputs) after its gamepad is unplugged, from 0 to \
+      65535. 0 disconnects the slot immediately. Any gamepad can reclaim a \
+      held slot by activating.\n\n\
+      - nickname is a name shown alongside the slot's number, and can have \
+      spaces in it.\n\n\
+      - mapping is the name of a mapping from the config, which changes what \
+      Switch button each gamepad button presses.\n\n\
+      - left_deadzone and right_deadzone are how far each stick has to be \
+      pushed before it registers, from 0 up to (but not including) 1.\n\n\
+      - reserved_device is the name of a gamepad, as listed by 'devices'. \
+      Other gamepads aren't assigned to the slot automatically, and the named \
+      gamepad goes to it first.\n\n\
+      'value' can be none to unset nickname, mapping, or reserved_device.\n\n\
+      Example, if you want to set the controller in slot 2 to a sideways left \
+      JoyCon:\n\n\
+      slot 1 set switch_pad JoyConLSide"
+  },
+  CommandSpec {
+    name: "release_all",
+    aliases: &["panic"],
+    synopsis: "release_all",
+    usage: &["release_all"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::ReleaseAll),
+    summary: "Releases every input on every slot.",
+    details: "Immediately sends a neutral state for every slot, as if nothing \
+      was pressed and all sticks were centered. Inputs still waiting on input \
+      delay are dropped.\n\n\
+      Holding Start, Select, and both bumpers on any gamepad does the same \
+      thing."
+  },
+  CommandSpec {
+    name: "status",
+    aliases: &[],
+    synopsis: "status (--watch)",
+    usage: &["status (--watch)"],
+    max_args: Some(1),
+    parse: parse_status,
+    summary: "Shows the state of the client and every slot.",
+    details: "Shows whether the client is running, how many packets were sent \
+      since it started, and for every slot: its controller type, input delay, \
+      gamepad, pressed buttons, and stick positions.\n\n\
+      With --watch, the status is redrawn twice a second until you press \
+      enter.\n\n\
+      Example, if you want to keep an eye on every slot:\n\n\
+      status --watch"
+  },
+  CommandSpec {
+    name: "stats",
+    aliases: &[],
+    synopsis: "stats",
+    usage: &["stats"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Stats),
+    summary: "Shows how well the client is keeping up.",
+    details: "Shows how many packets were sent and failed since the client \
+      started, how far off each send was from the 60 updates a second it's \
+      meant to make, how many events are read from the input adapter each \
+      second, how many it dropped for being unmapped, and how many are \
+      waiting on input delay.\n\n\
+      Averages and maximums are taken over the last full second. These are \
+      also shown by 'status' while the client is running."
+  },
+  CommandSpec {
+    name: "devices",
+    aliases: &[],
+    synopsis: "devices",
+    usage: &["devices"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Devices),
+    summary: "Lists every connected gamepad, its id, and its slot.",
+    details: "Lists every gamepad that is connected to this computer, along \
+      with the id to use with 'assign' and the slot it's assigned to."
+  },
+  CommandSpec {
+    name: "assign",
+    aliases: &[],
+    synopsis: "assign 'gamepad_id' 'i'",
+    usage: &["assign 'gamepad_id' 'i'"],
+    max_args: Some(2),
+    parse: parse_assign,
+    summary: "Assigns a gamepad to slot ('i' + 1).",
+    details: "'gamepad_id' is the id of a gamepad, as listed by 'devices'.\n\n\
+      'i' must be either 0 or a positive integer. It also represents the \
+      target index: slot numbers are always equal to 'i' + 1.\n\n\
+      The client must be running, and the slot must be free. If the gamepad \
+      is already in another slot, it's moved. Locked slots can still be \
+      assigned to this way.\n\n\
+      Example, if you want to put the gamepad with id 3 in slot 2:\n\n\
+      assign 3 1"
+  },
+  CommandSpec {
+    name: "kick",
+    aliases: &[],
+    synopsis: "kick 'i'",
+    usage: &["kick 'i'"],
+    max_args: Some(1),
+    parse: |args| args.slot("'i'").map(Command::Kick),
+    summary: "Disconnects the gamepad in slot ('i' + 1).",
+    details: "'i' must be either 0 or a positive integer. It also represents \
+      the target index: slot numbers are always equal to 'i' + 1.\n\n\
+      Disconnects the gamepad in the slot, or releases the slot if it's being \
+      held. The client must be running.\n\n\
+      Example, if you want to kick whoever is in slot 4:\n\n\
+      kick 3"
+  },
+  CommandSpec {
+    name: "swap",
+    aliases: &[],
+    synopsis: "swap 'a' 'b'",
+    usage: &["swap 'a' 'b'"],
+    max_args: Some(2),
+    parse: parse_swap,
+    summary: "Swaps the gamepads in slots ('a' + 1) and ('b' + 1).",
+    details: "'a' and 'b' must be either 0 or positive integers. They also \
+      represent the target indices: slot numbers are always equal to the \
+      index + 1.\n\n\
+      Swaps the gamepads in both slots, along with their controller types. \
+      One of the slots can be empty. The client must be running.\n\n\
+      Example, if you want to swap the gamepads in slots 1 and 2:\n\n\
+      swap 0 1"
+  },
+  CommandSpec {
+    name: "lock",
+    aliases: &[],
+    synopsis: "lock 'i'",
+    usage: &["lock 'i'"],
+    max_args: Some(1),
+    parse: |args| args.slot("'i'").map(Command::Lock),
+    summary: "Stops gamepads from being assigned to slot ('i' + 1) \
+      automatically.",
+    details: "'i' must be either 0 or a positive integer. It also represents \
+      the target index: slot numbers are always equal to 'i' + 1.\n\n\
+      A locked slot is skipped when gamepads activate, but gamepads can still \
+      be put there with 'assign'. Whoever is already in the slot stays \
+      there.\n\n\
+      Example, if you want to save slot 1 for yourself:\n\n\
+      lock 0"
+  },
+  CommandSpec {
+    name: "unlock",
+    aliases: &[],
+    synopsis: "unlock 'i'",
+    usage: &["unlock 'i'"],
+    max_args: Some(1),
+    parse: |args| args.slot("'i'").map(Command::Unlock),
+    summary: "Undoes 'lock'.",
+    details: "'i' must be either 0 or a positive integer. It also represents \
+      the target index: slot numbers are always equal to 'i' + 1.\n\n\
+      Example, if you want to unlock slot 1:\n\n\
+      unlock 0"
+  },
+  CommandSpec {
+    name: "profile",
+    aliases: &[],
+    synopsis: "profile 'action' ('name')",
+    usage: &[
+      "profile save 'name'", "profile load 'name'", "profile list",
+      "profile delete 'name'"
+    ],
+    max_args: Some(2),
+    parse: parse_profile,
+    summary: "Saves, loads, lists, or deletes profiles of server and slot \
+      settings.",
+    details: "A profile holds the server IP and port, the mappings, and the \
+      settings of every slot. 'save' saves the current ones under 'name', and \
+      'load' switches to them and saves them to the config.\n\n\
+      'name' can only contain letters, numbers, dashes, and underscores.\n\n\
+      Example, if you want to switch to a profile named mk8:\n\n\
+      profile load mk8"
+  },
+  CommandSpec {
+    name: "reload",
+    aliases: &[],
+    synopsis: "reload",
+    usage: &["reload"],
+    max_args: Some(0),
+    parse: |_| Ok(Command::Reload),
+    summary: "Reloads the config, applying whatever was changed in it.",
+    details: "Reloads the config and applies whatever was changed in it, \
+      without stopping the client. This also happens on its own whenever the \
+      config file is saved, or when the client receives SIGHUP (unless it was \
+      started from a terminal, where SIGHUP makes it exit). A slot in use \
+      whose switch_pad changed is reconnected as its new type, and settings \
+      given as command line arguments stay overridden.\n\n\
+      If the config is invalid, nothing is changed."
+  },
+  CommandSpec {
+    name: "source",
+    aliases: &[],
+    synopsis: "source 'file'",
+    usage: &["source 'file'"],
+    max_args: Some(1),
+    parse: parse_source,
+    summary: "Runs every command in a file, one per line.",
+    details: "'file' is the path of a text file with one command on each \
+      line. Blank lines and lines starting with # are skipped. Paths with \
+      spaces in them have to be quoted.\n\n\
+      The commands run in order, and stop at the first one that fails. The \
+      error says which line it was on. They also stop once one of them stops \
+      or exits the client.\n\n\
+      To run a file every time the client starts, set startup_script in the \
+      config to its path.\n\n\
+      Example, if your usual setup is in setup.txt:\n\n\
+      source setup.txt"
+  },
+  CommandSpec {
+    name: "set_auto_assign",
+    aliases: &[],
+    synopsis: "set_auto_assign 'auto_assign'",
+    usage: &["set_auto_assign 'auto_assign'"],
+    max_args: Some(1),
+    parse: parse_set_auto_assign,
+    summary: "Sets whether gamepads are assigned to slots as soon as they're \
+      connected.",
+    details: "'auto_assign' must be either true or false.\n\n\
+      When true, gamepads are assigned to the next free slot as soon as \
+      they're connected, in the order they were connected, without having to \
+      activate. Gamepads that are already connected when the client starts \
+      are assigned too.\n\n\
+      Example, if you want gamepads to be assigned automatically:\n\n\
+      set_auto_assign true"
+  },
+  CommandSpec {
+    name: "set_preferred_slot",
+    aliases: &[],
+    synopsis: "set_preferred_slot 'gamepad_id' 'i'",
+    usage: &["set_preferred_slot 'gamepad_id' 'i'"],
+    max_args: Some(2),
+    parse: parse_set_preferred_slot,
+    summary: "Sets the slot a gamepad is auto-assigned to.",
+    details: "'gamepad_id' is the id of a gamepad, as listed by 'devices'.\n\n\
+      'i' must be either 0 or a positive integer. It also represents the \
+      target index: slot numbers are always equal to 'i' + 1. 'i' can also be \
+      none to remove the preference.\n\n\
+      While auto-assigning, gamepads with the same name as this gamepad go to \
+      the slot if it's free, rather than the next free slot.\n\n\
+      Example, if you want the gamepad with id 2 to go to slot 3:\n\n\
+      set_preferred_slot 2 2"
+  }
+];
+
+// Returns the command that goes by a given name, if there is one.
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+  return COMMANDS.iter().find(|spec| spec.is_called(name));
+}
+
+// Returns the name of every command, including aliases.
+pub fn names() -> Vec<&'static str> {
+  let mut names: Vec<&'static str> = vec!();
+  for spec in COMMANDS.iter() {
+    names.push(spec.name);
+    names.extend(spec.aliases.iter());
+  }
+  return names;
+}
+
+// Returns the usage of a command along with where to find out more about it.
+fn usage_hint(name: &str) -> String {
+  return match find(name) {
+    Some(spec) => format!(
+      "{}\nType 'help {}' for full usage info.", spec.usage_lines(), spec.name
+    ),
+    None => "".to_string()
+  }
+}
+
+/**
+ * Either returns a list of all available commands, or provides specific usage
+ * info about a given command.
+ */
+pub fn help(name: Option<&str>) -> Result<String, String> {
+  return match name {
+    None => {
+      let mut lines: Vec<String> = vec!();
+      for spec in COMMANDS.iter() {
+        let mut line: String = format!("{}: {}", spec.synopsis, spec.summary);
+        for alias in spec.aliases {
+          line.push_str(&format!(" Also available as '{}'.", alias));
+        }
+        if !spec.details.is_empty() && spec.name != "help" {
+          line.push_str(
+            &format!(" Use 'help {}' for full usage info.", spec.name)
+          );
+        }
+        lines.push(line);
+      }
+      Ok(format!("\n{}", lines.join("\n\n")))
+    },
+    Some(name) => match find(name) {
+      Some(spec) => {
+        let mut text: String = format!("\n{}", spec.usage_lines());
+        for alias in spec.aliases {
+          text.push_str(
+            &format!(
+              "\n\n'{}' can be used in place of '{}'.", alias, spec.name
+            )
+          );
+        }
+        if !spec.details.is_empty() {
+          text.push_str(&format!("\n\n{}", spec.details));
+        }
+        Ok(text)
+      },
+      None => Err(format!("'{}' is not a valid command.", name))
+    }
+  }
+}
+
+/**
+ * Splits a line into words. Words are separated by any amount of whitespace,
+ * but anything in single or double quotes is kept as one word.
+ *
+ * Outside of single quotes, a backslash keeps a quote, backslash or whitespace
+ * after it as-is. Any other backslash is just a backslash, so Windows paths
+ * like C:\scripts\setup.txt don't need escaping.
+ */
+pub fn tokenize(line: &str) -> Result<Vec<String>, CommandError> {
+  let mut tokens: Vec<String> = vec!();
+  let mut token: Option<String> = None;
+  let mut quote: Option<char> = None;
+  let mut chars = line.chars().peekable();
+  while let Some(c) = chars.next() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some('\''), c) => token.get_or_insert(String::new()).push(c),
+      (_, '\\') => match chars.peek() {
+        Some(&next) if is_escapable(next) => {
+          token.get_or_insert(String::new()).push(next);
+          chars.next();
+        },
+        _ => token.get_or_insert(String::new()).push(c)
+      },
+      (Some(_), c) => token.get_or_insert(String::new()).push(c),
+      (None, '\'') | (None, '"') => {
+        // Quotes can make an empty word, like in: slot 0 set nickname ""
+        token.get_or_insert(String::new());
+        quote = Some(c);
+      },
+      (None, c) if c.is_whitespace() => {
+        if let Some(word) = token.take() {
+          tokens.push(word);
+        }
+      },
+      (None, c) => token.get_or_insert(String::new()).push(c)
+    }
+  }
+  if let Some(q) = quote {
+    return Err(CommandError::UnclosedQuote(q));
+  }
+  if let Some(word) = token {
+    tokens.push(word);
+  }
+  return Ok(tokens);
+}
+
+// Returns whether a backslash before a character keeps it as-is.
+fn is_escapable(c: char) -> bool {
+  return c == '\'' || c == '"' || c == '\\' || c.is_whitespace();
+}
+
+/**
+ * Parses a line into a command. A blank line isn't a command at all, so it's
+ * parsed into None instead.
+ */
+pub fn parse(line: &str) -> Result<Option<Command>, CommandError> {
+  let tokens: Vec<String> = match tokenize(line) {
+    Ok(tokens) => tokens,
+    Err(e) => return Err(e)
+  };
+  return match tokens.split_first() {
+    Some((name, args)) => match find(name) {
+      Some(spec) => spec.parse_args(args).map(Some),
+      None => Err(CommandError::UnknownCommand(name.to_string()))
+    },
+    None => Ok(None)
+  }
+}
+
+fn parse_help(args: &mut Arguments) -> Result<Command, CommandError> {
+  return Ok(Command::Help(args.optional().map(|name| name.to_string())));
+}
+
+fn parse_source(args: &mut Arguments) -> Result<Command, CommandError> {
+  return match args.next("'file'") {
+    Ok(path) => Ok(Command::Source(path.to_string())),
+    Err(e) => Err(e)
+  }
+}
+
+fn parse_status(args: &mut Arguments) -> Result<Command, CommandError> {
+  return match args.optional() {
+    Some("--watch") => Ok(Command::Status(true)),
+    Some(value) => Err(args.invalid("flag", value, "--watch")),
+    None => Ok(Command::Status(false))
+  }
+}
+
+fn parse_set_server_ip(args: &mut Arguments) -> Result<Command, CommandError> {
+  return match args.next("'server_ip'") {
+    Ok(server_ip) => Ok(Command::SetServerIp(server_ip.to_string())),
+    Err(e) => Err(e)
+  }
+}
+
+fn parse_set_auto_assign(
+  args: &mut Arguments
+) -> Result<Command, CommandError> {
+  return match args.parse::<bool>("'auto_assign'", "true or false") {
+    Ok(auto_assign) => Ok(Command::SetAutoAssign(auto_assign)),
+    Err(e) => Err(e)
+  }
+}
+
+fn parse_set_preferred_slot(
+  args: &mut Arguments
+) -> Result<Command, CommandError> {
+  let gamepad_id: usize = match args.gamepad_id() {
+    Ok(gamepad_id) => gamepad_id,
+    Err(e) => return Err(e)
+  };
+  return match args.next("'i'") {
+    Ok("none") => Ok(Command::SetPreferredSlot(gamepad_id, None)),
+    Ok(value) => match usize::from_str(value) {
+      Ok(i) => Ok(Command::SetPreferredSlot(gamepad_id, Some(i))),
+      Err(_) => Err(args.invalid("'i'", value, "a slot index like 0, or none"))
+    },
+    Err(e) => Err(e)
+  }
+}
+
+fn parse_assign(args: &mut Arguments) -> Result<Command, CommandError> {
+  return match (args.gamepad_id(), args.slot("'i'")) {
+    (Ok(gamepad_id), Ok(i)) => Ok(Command::Assign(gamepad_id, i)),
+    (Err(e), _) | (_, Err(e)) => Err(e)
+  }
+}
+
+fn parse_swap(args: &mut Arguments) -> Result<Command, CommandError> {
+  return match (args.slot("'a'"), args.slot("'b'")) {
+    (Ok(a), Ok(b)) => Ok(Command::Swap(a, b)),
+    (Err(e), _) | (_, Err(e)) => Err(e)
+  }
+}
+
+fn parse_slot(args: &mut Arguments) -> Result<Command, CommandError> {
+  let i: usize = match args.slot("'i'") {
+    Ok(i) => i,
+    Err(e) => return Err(e)
+  };
+  return match args.optional() {
+    Some("set") => {
+      let key: String = match args.next("'key'") {
+        Ok(key) => key.to_string(),
+        Err(e) => return Err(e)
+      };
+      match args.rest("'value'") {
+        Ok(value) => Ok(Command::SetSlot(i, key, value)),
+        Err(e) => Err(e)
+      }
+    },
+    Some(value) => Err(args.invalid("action", value, "set")),
+    None => Ok(Command::ShowSlot(i))
+  }
+}
+
+/**
+ * Parses a profile command. Names are checked here rather than when the
+ * profile is saved or loaded, so a bad name is caught along with the rest of
+ * the command's usage.
+ */
+fn parse_profile(args: &mut Arguments) -> Result<Command, CommandError> {
+  let action: &str = match args.next("'action'") {
+    Ok("list") => return Ok(Command::Profile(ProfileAction::List)),
+    Ok(action @ "save") | Ok(action @ "load") | Ok(action @ "delete") =>
+      action,
+    Ok(action) => return Err(
+      args.invalid("'action'", action, "save, load, list, or delete")
+    ),
+    Err(e) => return Err(e)
+  };
+  let name: String = match args.next("'name'") {
+    Ok(name) if config::is_valid_profile_name(name) => name.to_string(),
+    Ok(name) => return Err(
+      args.invalid(
+        "'name'", name, "made of only letters, numbers, dashes, and \
+        underscores"
+      )
+    ),
+    Err(e) => return Err(e)
+  };
+  return Ok(
+    Command::Profile(
+      match action {
+        "save" => ProfileAction::Save(name),
+        "load" => ProfileAction::Load(name),
+        _ => ProfileAction::Delete(name)
+      }
+    )
+  );
+}
+
+// Represents the arguments of a command, which are read from left to right.
+struct Arguments<'a> {
+  command: &'static str,
+  tokens: &'a [String],
+  next: usize
+}
+
+impl<'a> Arguments<'a> {
+  fn new(command: &'static str, tokens: &'a [String]) -> Arguments<'a> {
+    return Arguments {
+      command: command,
+      tokens: tokens,
+      next: 0
+    }
+  }
+
+  // Reads the next argument, if there is one.
+  fn optional(&mut self) -> Option<&'a str> {
+    let token: Option<&'a str> =
+      self.tokens.get(self.next).map(|token| token.as_str());
+    if token.is_some() {
+      self.next += 1;
+    }
+    return token;
+  }
+
+  // Reads the next argument, which has to be there.
+  fn next(&mut self, argument: &'static str) -> Result<&'a str, CommandError> {
+    return match self.optional() {
+      Some(token) => Ok(token),
+      None => Err(
+        CommandError::MissingArgument {
+          command: self.command,
+          argument: argument
+        }
+      )
+    }
+  }
+
+  // Reads the next argument as a given type.
+  fn parse<T: FromStr>(
+    &mut self, argument: &'static str, expected: &'static str
+  ) -> Result<T, CommandError> {
+    return match self.next(argument) {
+      Ok(token) => match T::from_str(token) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(self.invalid(argument, token, expected))
+      },
+      Err(e) => Err(e)
+    }
+  }
+
+  fn slot(&mut self, argument: &'static str) -> Result<usize, CommandError> {
+    return self.parse::<usize>(argument, "a slot index like 0");
+  }
+
+  fn gamepad_id(&mut self) -> Result<usize, CommandError> {
+    return self.parse::<usize>(
+      "'gamepad_id'", "a gamepad id, as listed by 'devices'"
+    );
+  }
+
+  /**
+   * Reads every argument that's left as one, joined by spaces. This lets
+   * values have spaces in them even without quotes, like nicknames.
+   */
+  fn rest(&mut self, argument: &'static str) -> Result<String, CommandError> {
+    if self.next >= self.tokens.len() {
+      return Err(
+        CommandError::MissingArgument {
+          command: self.command,
+          argument: argument
+        }
+      );
+    }
+    let rest: String = self.tokens[self.next..].join(" ");
+    self.next = self.tokens.len();
+    return Ok(rest);
+  }
+
+  // Makes sure there aren't any arguments left over.
+  fn finish(&self) -> Result<(), CommandError> {
+    return match self.tokens.get(self.next) {
+      Some(token) => Err(
+        CommandError::UnexpectedArgument {
+          command: self.command,
+          value: token.to_string()
+        }
+      ),
+      None => Ok(())
+    }
+  }
+
+  fn invalid(
+    &self, argument: &'static str, value: &str, expected: &'static str
+  ) -> CommandError {
+    return CommandError::InvalidArgument {
+      command: self.command,
+      argument: argument,
+      value: value.to_string(),
+      expected: expected
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn words(line: &str) -> Vec<String> {
+    return tokenize(line).unwrap();
+  }
+
+  #[test]
+  fn tokenize_splits_on_whitespace() {
+    assert_eq!(words("  slot 0\tset  nickname "), vec!(
+      "slot", "0", "set", "nickname"
+    ));
+    assert!(words("   ").is_empty());
+  }
+
+  #[test]
+  fn tokenize_keeps_quoted_words_together() {
+    assert_eq!(words("nickname \"Player  One\""), vec!(
+      "nickname", "Player  One"
+    ));
+    assert_eq!(words("nickname 'Player \"One\"'"), vec!(
+      "nickname", "Player \"One\""
+    ));
+    assert_eq!(words("a\"b c\"d"), vec!("ab cd"));
+    assert_eq!(words("nickname \"\""), vec!("nickname", ""));
+  }
+
+  #[test]
+  fn tokenize_escapes_quotes_backslashes_and_whitespace() {
+    assert_eq!(words("say \\\"hi\\\""), vec!("say", "\"hi\""));
+    assert_eq!(words("Player\\ One"), vec!("Player One"));
+    assert_eq!(words("a\\\\b"), vec!("a\\b"));
+    assert_eq!(words("\"a \\\" b\""), vec!("a \" b"));
+  }
+
+  #[test]
+  fn tokenize_keeps_other_backslashes() {
+    assert_eq!(words("source C:\\scripts\\setup.txt"), vec!(
+      "source", "C:\\scripts\\setup.txt"
+    ));
+    assert_eq!(words("source \"C:\\My Scripts\\setup.txt\""), vec!(
+      "source", "C:\\My Scripts\\setup.txt"
+    ));
+    assert_eq!(words("'C:\\scripts\\'"), vec!("C:\\scripts\\"));
+    assert_eq!(words("trailing\\"), vec!("trailing\\"));
+  }
+
+  #[test]
+  fn tokenize_refuses_unclosed_quotes() {
+    assert_eq!(tokenize("nickname \"Player"), Err(
+      CommandError::UnclosedQuote('"')
+    ));
+    assert_eq!(tokenize("nickname 'Player"), Err(
+      CommandError::UnclosedQuote('\'')
+    ));
+  }
+
+  #[test]
+  fn parse_skips_blank_lines() {
+    assert_eq!(parse(""), Ok(None));
+    assert_eq!(parse("  \t "), Ok(None));
+  }
+
+  #[test]
+  fn parse_reads_typed_arguments() {
+    assert_eq!(parse("start"), Ok(Some(Command::Start)));
+    assert_eq!(parse("panic"), Ok(Some(Command::ReleaseAll)));
+    assert_eq!(parse("status --watch"), Ok(Some(Command::Status(true))));
+    assert_eq!(parse("assign 3 1"), Ok(Some(Command::Assign(3, 1))));
+    assert_eq!(
+      parse("set_auto_assign true"),
+      Ok(Some(Command::SetAutoAssign(true)))
+    );
+    assert_eq!(
+      parse("set_preferred_slot 2 none"),
+      Ok(Some(Command::SetPreferredSlot(2, None)))
+    );
+    assert_eq!(
+      parse("slot 0 set nickname Player One"),
+      Ok(Some(
+        Command::SetSlot(0, "nickname".to_string(), "Player One".to_string())
+      ))
+    );
+    assert_eq!(
+      parse("profile save friday-night"),
+      Ok(Some(
+        Command::Profile(ProfileAction::Save("friday-night".to_string()))
+      ))
+    );
+    assert_eq!(
+      parse("source C:\\scripts\\setup.txt"),
+      Ok(Some(Command::Source("C:\\scripts\\setup.txt".to_string())))
+    );
+  }
+
+  #[test]
+  fn parse_refuses_unknown_commands() {
+    assert_eq!(
+      parse("launch"),
+      Err(CommandError::UnknownCommand("launch".to_string()))
+    );
+  }
+
+  #[test]
+  fn parse_refuses_missing_arguments() {
+    assert_eq!(
+      parse("assign 3"),
+      Err(
+        CommandError::MissingArgument { command: "assign", argument: "'i'" }
+      )
+    );
+    assert_eq!(
+      parse("slot 0 set nickname"),
+      Err(
+        CommandError::MissingArgument { command: "slot", argument: "'value'" }
+      )
+    );
+    assert_eq!(
+      parse("profile"),
+      Err(
+        CommandError::MissingArgument {
+          command: "profile",
+          argument: "'action'"
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn parse_refuses_invalid_arguments() {
+    assert_eq!(
+      parse("kick first"),
+      Err(
+        CommandError::InvalidArgument {
+          command: "kick",
+          argument: "'i'",
+          value: "first".to_string(),
+          expected: "a slot index like 0"
+        }
+      )
+    );
+    assert_eq!(
+      parse("set_auto_assign yes"),
+      Err(
+        CommandError::InvalidArgument {
+          command: "set_auto_assign",
+          argument: "'auto_assign'",
+          value: "yes".to_string(),
+          expected: "true or false"
+        }
+      )
+    );
+    assert_eq!(
+      parse("status --forever"),
+      Err(
+        CommandError::InvalidArgument {
+          command: "status",
+          argument: "flag",
+          value: "--forever".to_string(),
+          expected: "--watch"
+        }
+      )
+    );
+    assert_eq!(
+      parse("profile save 'Friday night'"),
+      Err(
+        CommandError::InvalidArgument {
+          command: "profile",
+          argument: "'name'",
+          value: "Friday night".to_string(),
+          expected: "made of only letters, numbers, dashes, and underscores"
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn parse_refuses_extra_arguments() {
+    assert_eq!(
+      parse("start now"),
+      Err(
+        CommandError::UnexpectedArgument {
+          command: "start",
+          value: "now".to_string()
+        }
+      )
+    );
+    assert_eq!(
+      parse("assign 3 1 2"),
+      Err(
+        CommandError::UnexpectedArgument {
+          command: "assign",
+          value: "2".to_string()
+        }
+      )
+    );
+    assert_eq!(
+      parse("profile list mk8"),
+      Err(
+        CommandError::UnexpectedArgument {
+          command: "profile",
+          value: "mk8".to_string()
+        }
+      )
+    );
+  }
+
+  #[test]
+  fn max_args_matches_the_longest_usage() {
+    for spec in COMMANDS.iter() {
+      if let Some(max_args) = spec.max_args {
+        let longest: usize = spec.usage.iter()
+          .map(|usage| usage.split_whitespace().count() - 1)
+          .max()
+          .unwrap();
+        assert_eq!(max_args, longest, "{}", spec.name);
+      }
+    }
+  }
+
+  #[test]
+  fn errors_about_a_command_show_its_usage() {
+    let e: String = parse("swap 0").err().unwrap().to_string();
+    assert!(e.starts_with("'swap' is missing 'b'.\n"));
+    assert!(e.contains("swap 'a' 'b'"));
+  }
+}
@@ -1,57 +1,329 @@
-use crate::input::switch::SwitchPad;
+use crate::input::{
+  adapter::common::InputButton,
+  switch::{
+    SwitchButton,
+    SwitchPad
+  }
+};
 
 use serde::{Serialize, Deserialize};
+use std::{
+  collections::{
+    HashMap,
+    HashSet
+  },
+  fs,
+  path::{
+    Path,
+    PathBuf
+  },
+  str::FromStr
+};
+use toml::value::{
+  Table,
+  Value
+};
+
+// The name of the directory the config is kept in, within the user's config.
+const APP_NAME: &str = "sys-hidplus-client-rs";
+
+/**
+ * The version of the config format. Whenever a field is added or changed in a
+ * way that older configs can't just be loaded with defaults, this is bumped and
+ * a step is added to migrate().
+ */
+pub const CONFIG_VERSION: u32 = 2;
+
+// The settings each slot has, which can be changed with 'slot 'i' set'.
+pub const SLOT_KEYS: [&str; 8] = [
+  "switch_pad", "input_delay", "hold_timeout", "nickname", "mapping",
+  "left_deadzone", "right_deadzone", "reserved_device"
+];
+
+/**
+ * Returns where the config is kept if no path is given.
+ *
+ * A config in the current directory is still used if there is one, so older
+ * setups keep working. Otherwise, it's kept in the user's config directory,
+ * which is ~/.config/sys-hidplus-client-rs on Linux, for example.
+ */
+pub fn default_config_path() -> PathBuf {
+  let local: PathBuf = PathBuf::from("./config.toml");
+  if local.exists() {
+    return local;
+  }
+  return match dirs::config_dir() {
+    Some(dir) => dir.join(APP_NAME).join("config.toml"),
+    None => local
+  }
+}
+
+// Returns the directory that profiles are kept in, next to a config.
+pub fn profiles_dir(config_path: &Path) -> PathBuf {
+  return match config_path.parent() {
+    Some(dir) => dir.join("profiles"),
+    None => PathBuf::from("profiles")
+  }
+}
+
+/**
+ * Returns whether a profile name only contains letters, numbers, dashes, and
+ * underscores.
+ */
+pub fn is_valid_profile_name(name: &str) -> bool {
+  return !name.is_empty() && name.chars().all(
+    |c| c.is_ascii_alphanumeric() || c == '-' || c == '_'
+  );
+}
+
+/**
+ * Returns where a profile with a given name is kept. Names can only contain
+ * letters, numbers, dashes, and underscores, so they can't point anywhere else.
+ */
+pub fn profile_path(config_path: &Path, name: &str) -> Result<PathBuf, String> {
+  if !is_valid_profile_name(name) {
+    return Err(
+      format!(
+        "'{}' isn't a valid profile name. Names can only contain letters, \
+        numbers, dashes, and underscores.",
+        name
+      )
+    );
+  }
+  return Ok(profiles_dir(config_path).join(format!("{}.toml", name)));
+}
+
+// Returns the names of every saved profile, in alphabetical order.
+pub fn list_profiles(config_path: &Path) -> Result<Vec<String>, String> {
+  let entries: fs::ReadDir = match fs::read_dir(profiles_dir(config_path)) {
+    Ok(entries) => entries,
+    Err(_) => return Ok(vec!())
+  };
+  let mut names: Vec<String> = vec!();
+  for entry in entries {
+    match entry {
+      Ok(entry) => {
+        let path: PathBuf = entry.path();
+        if path.extension().is_some_and(|extension| extension == "toml") {
+          if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+            names.push(name.to_string());
+          }
+        }
+      },
+      Err(e) => return Err(e.to_string())
+    }
+  }
+  names.sort();
+  return Ok(names);
+}
+
+/**
+ * Loads the config at a path, creating a default one if it doesn't exist yet.
+ *
+ * Configs from older versions are migrated and saved, after backing up the
+ * original to a .bak file next to it. A message saying so is returned with the
+ * config if that happens. Configs that don't fit the given number of slots are
+ * refused, with an error listing every problem with them.
+ */
+pub fn load(
+  path: &Path, num_slots: usize
+) -> Result<(Config, Option<String>), String> {
+  if !path.exists() {
+    return match confy::store_path(path, Config::default()) {
+      Ok(_) => Ok((Config::default(), None)),
+      Err(e) => Err(e.to_string())
+    }
+  }
+  let contents: String = match fs::read_to_string(path) {
+    Ok(contents) => contents,
+    Err(e) => return Err(e.to_string())
+  };
+  let mut table: Table = match toml::from_str(&contents) {
+    Ok(table) => table,
+    Err(e) => return Err(format!("{} isn't valid TOML: {}", path.display(), e))
+  };
+
+  // Configs from before versions were added are treated as version 0.
+  let version: u32 = match table.get("version") {
+    None => 0,
+    Some(Value::Integer(version)) if *version >= 0 => *version as u32,
+    Some(_) => return Err(
+      "version: must be 0 or a positive integer.".to_string()
+    )
+  };
+  if version > CONFIG_VERSION {
+    return Err(
+      format!(
+        "{} is from a newer version of the client (version {}), but only \
+        versions up to {} are supported.",
+        path.display(), version, CONFIG_VERSION
+      )
+    );
+  }
+  for from in version..CONFIG_VERSION {
+    migrate(&mut table, from);
+  }
+  table.insert("version".to_string(), Value::Integer(CONFIG_VERSION as i64));
+
+  let config: Config = match Value::Table(table).try_into() {
+    Ok(config) => config,
+    Err(e) => return Err(format!("{} couldn't be read: {}", path.display(), e))
+  };
+  let errors: Vec<String> = config.validate(num_slots);
+  if !errors.is_empty() {
+    return Err(
+      format!(
+        "{} has the following problems:\n{}",
+        path.display(),
+        c![format!("- {}", error), for error in errors].join("\n")
+      )
+    );
+  }
+
+  if version == CONFIG_VERSION {
+    return Ok((config, None));
+  }
+  let backup: PathBuf = PathBuf::from(format!("{}.bak", path.display()));
+  if let Err(e) = fs::copy(path, &backup) {
+    return Err(format!("Couldn't back up {}: {}", path.display(), e));
+  }
+  return match confy::store_path(path, &config) {
+    Ok(_) => Ok(
+      (
+        config,
+        Some(
+          format!(
+            "Config was migrated from version {} to {}. The old one was \
+            backed up to {}.",
+            version, CONFIG_VERSION, backup.display()
+          )
+        )
+      )
+    ),
+    Err(e) => Err(e.to_string())
+  }
+}
+
+/**
+ * Migrates a config from one version to the next.
+ *
+ * Fields that were only added are left alone, since they fall back to their
+ * defaults anyway. Only changes to existing fields need a step here, which is
+ * why configs from before versions were added (version 0) don't need one.
+ */
+#[allow(clippy::single_match)]
+fn migrate(table: &mut Table, from: u32) -> () {
+  match from {
+    // Slot settings were moved from one list per setting to a list of slots.
+    1 => {
+      let mut slots: Vec<Table> = vec!();
+      for (field, key) in [
+        ("switch_pads", "switch_pad"),
+        ("input_delays", "input_delay"),
+        ("hold_timeouts", "hold_timeout")
+      ] {
+        if let Some(Value::Array(values)) = table.remove(field) {
+          for (i, value) in values.into_iter().enumerate() {
+            if slots.len() <= i {
+              slots.push(Table::new());
+            }
+            slots[i].insert(key.to_string(), value);
+          }
+        }
+      }
+      if !slots.is_empty() {
+        table.insert(
+          "slot".to_string(),
+          Value::Array(c![Value::Table(slot), for slot in slots])
+        );
+      }
+    },
+    _ => ()
+  }
+}
 
 /**
  * A struct representing a configuration for a client.
+ * - version represents the version of the config format, which is used to
+ *   migrate older configs.
  * - server_ip represents the IP of the target Switch.
- * - switch_pads represent what Switch controller type each slot will emulate.
+ * - server_port represents the port the target Switch is listening on.
+ * - auto_assign represents whether gamepads are assigned to slots as soon as
+ *   they're connected, instead of having to activate.
+ * - startup_script represents a file of commands that are run whenever the
+ *   client starts up. Relative paths are relative to the config's folder.
+ * - cleanup_ms represents how long gamepads are disconnected for whenever the
+ *   client is stopped, in milliseconds.
+ * - reconnect_ms represents how long the client keeps trying to reach the
+ *   server after sending to it fails, in milliseconds, before it stops.
+ * - preferred_slots represent which slot index a gamepad with a given name is
+ *   auto-assigned to, if that slot is free.
+ * - mappings represent named sets of button remaps that slots can use, each
+ *   mapped from the name of a gamepad button to the Switch button it presses.
+ * - activations represent the button chords that assign a gamepad to a slot.
+ * - slots represent the settings of each slot, and are written as [[slot]].
+ *
+ * Missing fields fall back to their defaults, so configs from older versions
+ * can still be loaded.
  */
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+  version: u32,
   server_ip: String,
-  switch_pads: Vec<SwitchPad>,
-  input_delays: Vec<u8>
-  /*
-  switch_pad_1: Option<SwitchPad>,
-  input_delay_1: i8,
-  switch_pad_2: Option<SwitchPad>,
-  input_delay_2: i8,
-  switch_pad_3: Option<SwitchPad>,
-  input_delay_3: i8,
-  switch_pad_4: Option<SwitchPad>,
-  input_delay_4: i8
-  */
+  server_port: u16,
+  auto_assign: bool,
+  startup_script: Option<PathBuf>,
+  cleanup_ms: u64,
+  reconnect_ms: u64,
+  // Tables have to come last for the config to be serialized.
+  preferred_slots: HashMap<String, usize>,
+  mappings: HashMap<String, HashMap<String, SwitchButton>>,
+  activations: Vec<Activation>,
+  #[serde(rename = "slot")]
+  slots: Vec<SlotConfig>
 }
 
 impl Default for Config {
   fn default() -> Config {
     return Config {
+      version: CONFIG_VERSION,
       server_ip: "".to_string(),
-      switch_pads: c!(SwitchPad::ProController, for _i in 0..4),
-      input_delays: c!(0, for _i in 0..4)
-      /*
-      switch_pad_1: Some(SwitchPad::ProController),
-      input_delay_1: 0,
-      switch_pad_2: Some(SwitchPad::ProController),
-      input_delay_2: 0,
-      switch_pad_3: Some(SwitchPad::ProController),
-      input_delay_3: 0,
-      switch_pad_4: Some(SwitchPad::ProController),
-      input_delay_4: 0
-      */
+      server_port: 8000,
+      auto_assign: false,
+      startup_script: None,
+      cleanup_ms: 3000,
+      reconnect_ms: 30000,
+      preferred_slots: HashMap::new(),
+      mappings: HashMap::new(),
+      activations: vec!(
+        Activation::new(vec!(InputButton::RightBumper), None, 0)
+      ),
+      slots: c!(SlotConfig::default(), for _i in 0..4)
     }
   }
 }
 
 impl Config {
   pub fn new(
-    server_ip: String, switch_pads: Vec<SwitchPad>, input_delays: Vec<u8>
+    server_ip: String, server_port: u16, auto_assign: bool,
+    startup_script: Option<PathBuf>, cleanup_ms: u64, reconnect_ms: u64,
+    preferred_slots: HashMap<String, usize>,
+    mappings: HashMap<String, HashMap<String, SwitchButton>>,
+    activations: Vec<Activation>, slots: Vec<SlotConfig>
   ) -> Config {
     return Config {
+      version: CONFIG_VERSION,
       server_ip: server_ip,
-      switch_pads: switch_pads,
-      input_delays: input_delays
+      server_port: server_port,
+      auto_assign: auto_assign,
+      startup_script: startup_script,
+      cleanup_ms: cleanup_ms,
+      reconnect_ms: reconnect_ms,
+      preferred_slots: preferred_slots,
+      mappings: mappings,
+      activations: activations,
+      slots: slots
     }
   }
 
@@ -59,11 +331,768 @@ impl Config {
     return &self.server_ip;
   }
 
-  pub fn get_switch_pads(&self) -> &Vec<SwitchPad> {
-    return &self.switch_pads;
+  pub fn get_server_port(&self) -> &u16 {
+    return &self.server_port;
+  }
+
+  pub fn get_auto_assign(&self) -> &bool {
+    return &self.auto_assign;
+  }
+
+  pub fn get_startup_script(&self) -> &Option<PathBuf> {
+    return &self.startup_script;
+  }
+
+  pub fn get_cleanup_ms(&self) -> &u64 {
+    return &self.cleanup_ms;
+  }
+
+  pub fn get_reconnect_ms(&self) -> &u64 {
+    return &self.reconnect_ms;
+  }
+
+  pub fn get_preferred_slots(&self) -> &HashMap<String, usize> {
+    return &self.preferred_slots;
+  }
+
+  pub fn get_mappings(
+    &self
+  ) -> &HashMap<String, HashMap<String, SwitchButton>> {
+    return &self.mappings;
+  }
+
+  pub fn get_activations(&self) -> &Vec<Activation> {
+    return &self.activations;
+  }
+
+  pub fn get_slots(&self) -> &Vec<SlotConfig> {
+    return &self.slots;
+  }
+
+  /**
+   * Returns every problem with this config for a given number of slots, each
+   * starting with the field it's in. A config without any problems is valid.
+   */
+  pub fn validate(&self, num_slots: usize) -> Vec<String> {
+    let mut errors: Vec<String> = vec!();
+    if self.server_port == 0 {
+      errors.push("server_port: must be between 1 and 65535.".to_string());
+    }
+    if self.slots.len() != num_slots {
+      errors.push(
+        format!(
+          "slot: must have exactly {} entries (one per slot), but has {}.",
+          num_slots, self.slots.len()
+        )
+      );
+    }
+    let mut mapping_names: Vec<&String> = self.mappings.keys().collect();
+    mapping_names.sort();
+    for name in mapping_names {
+      if let Err(e) = parse_mapping(&self.mappings[name]) {
+        errors.push(format!("mappings.{}: {}", name, e));
+      }
+    }
+    let mut reserved: HashMap<&String, usize> = HashMap::new();
+    for (i, slot) in self.slots.iter().enumerate() {
+      for e in slot.validate(&self.mappings) {
+        errors.push(format!("slot[{}].{}", i, e));
+      }
+      if let Some(device) = &slot.reserved_device {
+        match reserved.get(device) {
+          Some(j) => errors.push(
+            format!(
+              "slot[{}].reserved_device: '{}' is already reserved by slot[{}].",
+              i, device, j
+            )
+          ),
+          None => {
+            reserved.insert(device, i);
+          }
+        }
+      }
+    }
+    let mut names: Vec<&String> = self.preferred_slots.keys().collect();
+    names.sort();
+    for name in names {
+      let i: usize = self.preferred_slots[name];
+      if i >= num_slots {
+        errors.push(
+          format!(
+            "preferred_slots.\"{}\": slot index must be less than {}, but is \
+            {}.",
+            name, num_slots, i
+          )
+        );
+      }
+    }
+    for (i, activation) in self.activations.iter().enumerate() {
+      if activation.buttons.is_empty() {
+        errors.push(
+          format!("activations[{}].buttons: must have at least one button.", i)
+        );
+      }
+      if activation.switch_pad == Some(SwitchPad::Disconnected) {
+        errors.push(
+          format!(
+            "activations[{}].switch_pad: can't be Disconnected, since that \
+            would never connect anything.",
+            i
+          )
+        );
+      }
+    }
+    return errors;
+  }
+}
+
+/**
+ * Parses a mapping from the config, where gamepad buttons are given by name.
+ * Fails on the first name that isn't a gamepad button.
+ */
+pub fn parse_mapping(
+  mapping: &HashMap<String, SwitchButton>
+) -> Result<HashMap<InputButton, SwitchButton>, String> {
+  let mut parsed: HashMap<InputButton, SwitchButton> = HashMap::new();
+  for (name, switch_button) in mapping {
+    match InputButton::from_str(name) {
+      Ok(button) => {
+        parsed.insert(button, *switch_button);
+      },
+      Err(_) => return Err(format!("'{}' isn't a gamepad button.", name))
+    }
+  }
+  return Ok(parsed);
+}
+
+/**
+ * A struct representing the settings of a single slot.
+ * - switch_pad represents what Switch controller type the slot will emulate.
+ * - input_delay represents how many frames the slot's inputs are delayed by.
+ * - hold_timeout represents how many seconds the slot is kept connected after
+ *   its gamepad is physically disconnected. 0 disables holding.
+ * - nickname represents a name shown alongside the slot's number.
+ * - mapping represents the name of the mapping the slot uses, if any.
+ * - left_deadzone and right_deadzone represent how far each stick has to be
+ *   pushed before it registers, from 0 up to (but not including) 1.
+ * - reserved_device represents the name of a gamepad the slot is kept for.
+ *   Other gamepads aren't assigned to it automatically.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlotConfig {
+  switch_pad: SwitchPad,
+  input_delay: u8,
+  hold_timeout: u16,
+  nickname: Option<String>,
+  mapping: Option<String>,
+  left_deadzone: f32,
+  right_deadzone: f32,
+  reserved_device: Option<String>
+}
+
+impl Default for SlotConfig {
+  fn default() -> SlotConfig {
+    return SlotConfig {
+      switch_pad: SwitchPad::ProController,
+      input_delay: 0,
+      hold_timeout: 0,
+      nickname: None,
+      mapping: None,
+      left_deadzone: 0.0,
+      right_deadzone: 0.0,
+      reserved_device: None
+    }
+  }
+}
+
+impl SlotConfig {
+  pub fn get_switch_pad(&self) -> &SwitchPad {
+    return &self.switch_pad;
+  }
+
+  pub fn get_input_delay(&self) -> &u8 {
+    return &self.input_delay;
+  }
+
+  pub fn get_hold_timeout(&self) -> &u16 {
+    return &self.hold_timeout;
+  }
+
+  pub fn get_nickname(&self) -> &Option<String> {
+    return &self.nickname;
+  }
+
+  pub fn get_mapping(&self) -> &Option<String> {
+    return &self.mapping;
+  }
+
+  pub fn get_deadzones(&self) -> (f32, f32) {
+    return (self.left_deadzone, self.right_deadzone);
+  }
+
+  pub fn get_reserved_device(&self) -> &Option<String> {
+    return &self.reserved_device;
+  }
+
+  // Returns the value of a setting as text, where unset settings are 'none'.
+  pub fn get(&self, key: &str) -> Result<String, String> {
+    let optional = |value: &Option<String>| -> String {
+      return value.clone().unwrap_or("none".to_string());
+    };
+    return match key {
+      "switch_pad" => Ok(format!("{:?}", self.switch_pad)),
+      "input_delay" => Ok(self.input_delay.to_string()),
+      "hold_timeout" => Ok(self.hold_timeout.to_string()),
+      "nickname" => Ok(optional(&self.nickname)),
+      "mapping" => Ok(optional(&self.mapping)),
+      "left_deadzone" => Ok(self.left_deadzone.to_string()),
+      "right_deadzone" => Ok(self.right_deadzone.to_string()),
+      "reserved_device" => Ok(optional(&self.reserved_device)),
+      _ => Err(
+        format!(
+          "'{}' isn't a slot setting. It must be one of: {}.",
+          key, SLOT_KEYS.join(", ")
+        )
+      )
+    }
+  }
+
+  /**
+   * Sets a setting from text. Settings that can be unset are unset with
+   * 'none'. Only the value itself is checked here; use validate() to check it
+   * against the rest of the config.
+   */
+  pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+    let optional = |value: &str| -> Option<String> {
+      if value == "none" {
+        return None;
+      } else {
+        return Some(value.to_string());
+      }
+    };
+    match key {
+      "switch_pad" => match SwitchPad::from_str(value) {
+        Ok(switch_pad) => self.switch_pad = switch_pad,
+        Err(_) => return Err(
+          format!(
+            "'{}' isn't a controller type. It must be one of: Disconnected, \
+            ProController, JoyConLSide, or JoyConRSide.",
+            value
+          )
+        )
+      },
+      "input_delay" => match value.parse::<u8>() {
+        Ok(input_delay) => self.input_delay = input_delay,
+        Err(_) => return Err(
+          format!("'{}' isn't a number of frames from 0 to 255.", value)
+        )
+      },
+      "hold_timeout" => match value.parse::<u16>() {
+        Ok(hold_timeout) => self.hold_timeout = hold_timeout,
+        Err(_) => return Err(
+          format!("'{}' isn't a number of seconds from 0 to 65535.", value)
+        )
+      },
+      "nickname" => self.nickname = optional(value),
+      "mapping" => self.mapping = optional(value),
+      "left_deadzone" | "right_deadzone" => match value.parse::<f32>() {
+        Ok(deadzone) if (0.0..1.0).contains(&deadzone) => {
+          if key == "left_deadzone" {
+            self.left_deadzone = deadzone;
+          } else {
+            self.right_deadzone = deadzone;
+          }
+        },
+        _ => return Err(
+          format!(
+            "'{}' isn't a deadzone. It must be at least 0 and less than 1.",
+            value
+          )
+        )
+      },
+      "reserved_device" => self.reserved_device = optional(value),
+      _ => return Err(
+        format!(
+          "'{}' isn't a slot setting. It must be one of: {}.",
+          key, SLOT_KEYS.join(", ")
+        )
+      )
+    }
+    return Ok(());
+  }
+
+  /**
+   * Returns every problem with this slot's settings, each starting with the
+   * setting it's in. Mappings are checked against the ones given.
+   */
+  pub fn validate(
+    &self, mappings: &HashMap<String, HashMap<String, SwitchButton>>
+  ) -> Vec<String> {
+    let mut errors: Vec<String> = vec!();
+    for (key, deadzone) in [
+      ("left_deadzone", self.left_deadzone),
+      ("right_deadzone", self.right_deadzone)
+    ] {
+      if !(0.0..1.0).contains(&deadzone) {
+        errors.push(
+          format!("{}: must be at least 0 and less than 1.", key)
+        );
+      }
+    }
+    if let Some(mapping) = &self.mapping {
+      if !mappings.contains_key(mapping) {
+        errors.push(
+          format!("mapping: there's no mapping named '{}'.", mapping)
+        );
+      }
+    }
+    return errors;
+  }
+}
+
+/**
+ * A struct representing a named setup that can be switched to, which bundles
+ * the server settings, the mappings, and the slot settings from a config.
+ */
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+  server_ip: String,
+  server_port: u16,
+  mappings: HashMap<String, HashMap<String, SwitchButton>>,
+  #[serde(rename = "slot")]
+  slots: Vec<SlotConfig>
+}
+
+impl Default for Profile {
+  fn default() -> Profile {
+    let config: Config = Config::default();
+    return Profile {
+      server_ip: config.server_ip,
+      server_port: config.server_port,
+      mappings: config.mappings,
+      slots: config.slots
+    }
+  }
+}
+
+impl Profile {
+  pub fn new(
+    server_ip: String, server_port: u16,
+    mappings: HashMap<String, HashMap<String, SwitchButton>>,
+    slots: Vec<SlotConfig>
+  ) -> Profile {
+    return Profile {
+      server_ip: server_ip,
+      server_port: server_port,
+      mappings: mappings,
+      slots: slots
+    }
+  }
+
+  pub fn get_server_ip(&self) -> &String {
+    return &self.server_ip;
+  }
+
+  pub fn get_server_port(&self) -> &u16 {
+    return &self.server_port;
+  }
+
+  pub fn get_mappings(
+    &self
+  ) -> &HashMap<String, HashMap<String, SwitchButton>> {
+    return &self.mappings;
+  }
+
+  pub fn get_slots(&self) -> &Vec<SlotConfig> {
+    return &self.slots;
+  }
+}
+
+/**
+ * A struct representing a gesture that assigns a gamepad to a slot.
+ * - buttons represent the chord that has to be pressed at the same time.
+ * - switch_pad represents what Switch controller type the slot will emulate. If
+ *   left out, the slot's own type is used instead.
+ * - hold_ms represents how long the chord has to be held for, in milliseconds.
+ */
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activation {
+  buttons: Vec<InputButton>,
+  #[serde(default)]
+  switch_pad: Option<SwitchPad>,
+  #[serde(default)]
+  hold_ms: u64
+}
+
+impl Activation {
+  pub fn new(
+    buttons: Vec<InputButton>, switch_pad: Option<SwitchPad>, hold_ms: u64
+  ) -> Activation {
+    return Activation {
+      buttons: buttons,
+      switch_pad: switch_pad,
+      hold_ms: hold_ms
+    }
+  }
+
+  pub fn get_buttons(&self) -> &Vec<InputButton> {
+    return &self.buttons;
+  }
+
+  pub fn get_switch_pad(&self) -> &Option<SwitchPad> {
+    return &self.switch_pad;
+  }
+
+  pub fn get_hold_ms(&self) -> &u64 {
+    return &self.hold_ms;
+  }
+
+  /**
+   * Returns whether this activation's chord is satisfied by a set of pressed
+   * buttons. An activation without any buttons is never satisfied.
+   */
+  pub fn is_satisfied_by(&self, pressed: &HashSet<InputButton>) -> bool {
+    return !self.buttons.is_empty()
+      && self.buttons.iter().all(|button| pressed.contains(button));
+  }
+}
+
+/**
+ * A struct representing settings that were given on the command line, which
+ * take priority over the ones in a loaded config.
+ * - switch_pads and input_delays are mapped from slot index to their values.
+ *
+ * Overridden settings are only used for as long as the client runs. Whenever
+ * the config is saved, the values it was loaded with are kept instead, unless
+ * an override is cleared first.
+ */
+#[derive(Clone, Default)]
+pub struct ConfigOverrides {
+  server_ip: Option<String>,
+  server_port: Option<u16>,
+  switch_pads: HashMap<usize, SwitchPad>,
+  input_delays: HashMap<usize, u8>
+}
+
+impl ConfigOverrides {
+  pub fn new(
+    server_ip: Option<String>, server_port: Option<u16>,
+    switch_pads: HashMap<usize, SwitchPad>, input_delays: HashMap<usize, u8>
+  ) -> ConfigOverrides {
+    return ConfigOverrides {
+      server_ip: server_ip,
+      server_port: server_port,
+      switch_pads: switch_pads,
+      input_delays: input_delays
+    }
+  }
+
+  // Returns how many settings are being overridden.
+  pub fn len(&self) -> usize {
+    return self.server_ip.iter().count() + self.server_port.iter().count()
+      + self.switch_pads.len() + self.input_delays.len();
+  }
+
+  pub fn is_empty(&self) -> bool {
+    return self.len() == 0;
+  }
+
+  /**
+   * Clears overrides, so that the values they replaced are no longer kept when
+   * saving. These are used when a setting is changed on purpose.
+   */
+  pub fn clear_server_ip(&mut self) -> () {
+    self.server_ip = None;
+  }
+
+  pub fn clear_switch_pad(&mut self, i: &usize) -> () {
+    self.switch_pads.remove(i);
+  }
+
+  pub fn clear_input_delay(&mut self, i: &usize) -> () {
+    self.input_delays.remove(i);
+  }
+
+  pub fn clear(&mut self) -> () {
+    *self = ConfigOverrides::default();
+  }
+
+  /**
+   * Returns a config with these overrides applied to it. Fails if a slot is
+   * overridden that the config doesn't have.
+   */
+  pub fn apply(&self, config: Config) -> Result<Config, String> {
+    let mut config: Config = config;
+    if let Some(server_ip) = &self.server_ip {
+      config.server_ip = server_ip.to_string();
+    }
+    if let Some(server_port) = &self.server_port {
+      config.server_port = *server_port;
+    }
+    for (i, switch_pad) in &self.switch_pads {
+      match config.slots.get_mut(*i) {
+        Some(slot) => slot.switch_pad = *switch_pad,
+        None => return Err(format!("There's no slot with an index of {}.", i))
+      }
+    }
+    for (i, input_delay) in &self.input_delays {
+      match config.slots.get_mut(*i) {
+        Some(slot) => slot.input_delay = *input_delay,
+        None => return Err(format!("There's no slot with an index of {}.", i))
+      }
+    }
+    return Ok(config);
+  }
+
+  /**
+   * Undoes these overrides on a config, by putting back the values from the
+   * config that was saved before.
+   */
+  pub fn restore(&self, config: Config, saved: &Config) -> Config {
+    let mut config: Config = config;
+    if self.server_ip.is_some() {
+      config.server_ip = saved.server_ip.to_string();
+    }
+    if self.server_port.is_some() {
+      config.server_port = saved.server_port;
+    }
+    for i in self.switch_pads.keys() {
+      if let (Some(slot), Some(saved_slot)) =
+        (config.slots.get_mut(*i), saved.slots.get(*i)) {
+        slot.switch_pad = saved_slot.switch_pad;
+      }
+    }
+    for i in self.input_delays.keys() {
+      if let (Some(slot), Some(saved_slot)) =
+        (config.slots.get_mut(*i), saved.slots.get(*i)) {
+        slot.input_delay = saved_slot.input_delay;
+      }
+    }
+    return config;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use std::{
+    env,
+    process
+  };
+
+  // Returns a path for a config that doesn't exist yet, unique to a test.
+  fn temp_config(name: &str) -> PathBuf {
+    let dir: PathBuf = env::temp_dir()
+      .join(format!("client-rs-config-{}-{}", process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    return dir.join("config.toml");
+  }
+
+  #[test]
+  fn default_config_is_valid() {
+    assert!(Config::default().validate(4).is_empty());
+  }
+
+  #[test]
+  fn validate_lists_every_problem() {
+    let mut config: Config = Config {
+      server_port: 0,
+      ..Config::default()
+    };
+    config.slots.pop();
+    config.mappings.insert(
+      "swapped".to_string(),
+      vec!(("Nope".to_string(), SwitchButton::A)).into_iter().collect()
+    );
+    config.slots[0].mapping = Some("missing".to_string());
+    config.slots[1].left_deadzone = 1.5;
+    config.slots[1].reserved_device = Some("Pad".to_string());
+    config.slots[2].reserved_device = Some("Pad".to_string());
+    config.preferred_slots.insert("Pad".to_string(), 4);
+    config.activations.push(Activation::new(vec!(), None, 0));
+    config.activations.push(
+      Activation::new(
+        vec!(InputButton::Start), Some(SwitchPad::Disconnected), 0
+      )
+    );
+    let errors: Vec<String> = config.validate(4);
+    assert_eq!(
+      errors,
+      vec!(
+        "server_port: must be between 1 and 65535.",
+        "slot: must have exactly 4 entries (one per slot), but has 3.",
+        "mappings.swapped: 'Nope' isn't a gamepad button.",
+        "slot[0].mapping: there's no mapping named 'missing'.",
+        "slot[1].left_deadzone: must be at least 0 and less than 1.",
+        "slot[2].reserved_device: 'Pad' is already reserved by slot[1].",
+        "preferred_slots.\"Pad\": slot index must be less than 4, but is 4.",
+        "activations[1].buttons: must have at least one button.",
+        "activations[2].switch_pad: can't be Disconnected, since that would \
+        never connect anything."
+      )
+    );
+  }
+
+  #[test]
+  fn migrate_moves_slot_lists_into_slots() {
+    let mut table: Table = toml::from_str(
+      "switch_pads = [\"JoyConLSide\", \"JoyConRSide\"]\n\
+      input_delays = [1, 2]\n\
+      hold_timeouts = [5]\n"
+    ).unwrap();
+    migrate(&mut table, 1);
+    assert!(table.get("switch_pads").is_none());
+    assert!(table.get("input_delays").is_none());
+    assert!(table.get("hold_timeouts").is_none());
+    let expected: Table = toml::from_str(
+      "[[slot]]\n\
+      switch_pad = \"JoyConLSide\"\n\
+      input_delay = 1\n\
+      hold_timeout = 5\n\
+      [[slot]]\n\
+      switch_pad = \"JoyConRSide\"\n\
+      input_delay = 2\n"
+    ).unwrap();
+    assert_eq!(table, expected);
+  }
+
+  #[test]
+  fn migrate_leaves_configs_without_slot_lists_alone() {
+    let mut table: Table = toml::from_str("server_ip = \"10.0.0.2\"").unwrap();
+    let expected: Table = table.clone();
+    migrate(&mut table, 1);
+    assert_eq!(table, expected);
+  }
+
+  #[test]
+  fn load_creates_a_default_config() {
+    let path: PathBuf = temp_config("default");
+    let (config, migrated) = load(&path, 4).unwrap();
+    assert!(path.exists());
+    assert!(migrated.is_none());
+    assert_eq!(config.version, CONFIG_VERSION);
+    assert_eq!(config.slots.len(), 4);
+  }
+
+  #[test]
+  fn load_fills_in_missing_fields() {
+    let path: PathBuf = temp_config("missing");
+    fs::write(
+      &path,
+      format!("version = {}\nserver_ip = \"10.0.0.2\"\n", CONFIG_VERSION)
+    ).unwrap();
+    let (config, migrated) = load(&path, 4).unwrap();
+    assert!(migrated.is_none());
+    assert_eq!(config.get_server_ip(), "10.0.0.2");
+    assert_eq!(*config.get_server_port(), 8000);
+    assert_eq!(config.get_slots().len(), 4);
+  }
+
+  #[test]
+  fn load_refuses_newer_versions() {
+    let path: PathBuf = temp_config("newer");
+    fs::write(&path, format!("version = {}\n", CONFIG_VERSION + 1)).unwrap();
+    let e: String = load(&path, 4).err().unwrap();
+    assert!(e.contains("is from a newer version of the client"));
+  }
+
+  #[test]
+  fn load_refuses_invalid_versions() {
+    let path: PathBuf = temp_config("negative");
+    fs::write(&path, "version = -1\n").unwrap();
+    assert_eq!(
+      load(&path, 4).err().unwrap(),
+      "version: must be 0 or a positive integer."
+    );
+  }
+
+  #[test]
+  fn load_refuses_invalid_configs() {
+    let path: PathBuf = temp_config("invalid");
+    fs::write(
+      &path,
+      format!("version = {}\nserver_port = 0\n", CONFIG_VERSION)
+    ).unwrap();
+    let e: String = load(&path, 4).err().unwrap();
+    assert!(e.ends_with(
+      "has the following problems:\n- server_port: must be between 1 and \
+      65535."
+    ));
+  }
+
+  #[test]
+  fn load_migrates_unversioned_configs() {
+    let path: PathBuf = temp_config("unversioned");
+    let original: &str = "server_ip = \"10.0.0.2\"\n\
+      switch_pads = [\"JoyConLSide\", \"JoyConRSide\", \"ProController\", \
+      \"Disconnected\"]\n\
+      input_delays = [1, 2, 3, 4]\n";
+    fs::write(&path, original).unwrap();
+    let (config, migrated) = load(&path, 4).unwrap();
+    assert!(
+      migrated.unwrap().starts_with("Config was migrated from version 0")
+    );
+    assert_eq!(config.get_server_ip(), "10.0.0.2");
+    assert_eq!(*config.slots[0].get_switch_pad(), SwitchPad::JoyConLSide);
+    assert_eq!(*config.slots[3].get_switch_pad(), SwitchPad::Disconnected);
+    assert_eq!(*config.slots[2].get_input_delay(), 3);
+
+    let backup: String =
+      fs::read_to_string(format!("{}.bak", path.display())).unwrap();
+    assert_eq!(backup, original);
+    let (_, migrated) = load(&path, 4).unwrap();
+    assert!(migrated.is_none());
+  }
+
+  // Returns overrides for the server IP, and slot 1's controller type.
+  fn overrides() -> ConfigOverrides {
+    return ConfigOverrides::new(
+      Some("10.0.0.2".to_string()),
+      None,
+      vec!((1, SwitchPad::JoyConRSide)).into_iter().collect(),
+      HashMap::new()
+    );
+  }
+
+  #[test]
+  fn overrides_apply_on_top_of_config() {
+    let config: Config = overrides().apply(Config::default()).unwrap();
+    assert_eq!(config.server_ip, "10.0.0.2");
+    assert_eq!(config.slots[1].switch_pad, SwitchPad::JoyConRSide);
+    assert_eq!(config.slots[0].switch_pad, SwitchPad::ProController);
+
+    let mut config: Config = Config::default();
+    config.slots.truncate(1);
+    assert_eq!(
+      overrides().apply(config).err(),
+      Some("There's no slot with an index of 1.".to_string())
+    );
+  }
+
+  #[test]
+  fn overrides_restored_but_other_changes_kept() {
+    let saved: Config = Config::default();
+    let mut config: Config = overrides().apply(Config::default()).unwrap();
+    config.cleanup_ms = 500;
+    let restored: Config = overrides().restore(config, &saved);
+    assert_eq!(restored.server_ip, saved.server_ip);
+    assert_eq!(restored.slots[1].switch_pad, SwitchPad::ProController);
+    assert_eq!(restored.cleanup_ms, 500);
   }
 
-  pub fn get_input_delays(&self) -> &Vec<u8> {
-    return &self.input_delays;
+  #[test]
+  fn cleared_overrides_not_restored() {
+    let saved: Config = Config::default();
+    let mut overrides: ConfigOverrides = overrides();
+    let config: Config = overrides.apply(Config::default()).unwrap();
+    overrides.clear_server_ip();
+    let restored: Config = overrides.restore(config, &saved);
+    assert_eq!(restored.server_ip, "10.0.0.2");
+    assert_eq!(restored.slots[1].switch_pad, SwitchPad::ProController);
+    assert_eq!(overrides.len(), 1);
   }
 }
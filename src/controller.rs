@@ -1,94 +1,363 @@
 use crate::{ 
+  command::{
+    self,
+    Command,
+    ProfileAction
+  },
   input::{
     adapter::common::{
       InputButton,
       InputEvent,
       InputAdapter
     },
-    switch::SwitchPad
+    switch::{
+      EmulatedPad,
+      SwitchButton,
+      SwitchPad
+    }
+  },
+  config::{
+    self,
+    Activation,
+    Config,
+    ConfigOverrides,
+    Profile,
+    SlotConfig,
+    SLOT_KEYS
   },
-  config::Config,
   model::ClientModel,
+  outage::Outage,
+  stats::{
+    ClientStats,
+    StatsTracker
+  },
+  remote::{
+    event::{
+      ClientEvent,
+      EventBus
+    },
+    RemoteCall,
+    RemoteRequest,
+    RemoteResponse
+  },
+  status::{
+    ClientStatus,
+    SlotStatus
+  },
   view::common::ClientView 
 };
 
 use confy::ConfyError;
+use crossbeam_channel::Receiver;
 use std::{
   collections::{
     HashMap,
     HashSet
   },
-  process,
-  str::FromStr
+  fs,
+  path::{
+    Path,
+    PathBuf
+  },
+  sync::{
+    Arc,
+    atomic::{
+      AtomicBool,
+      Ordering
+    }
+  },
+  time
 };
 
+// How long there is between each update, which is 60 times a second.
+pub const UPDATE_INTERVAL: time::Duration =
+  time::Duration::from_nanos(1_000_000_000 / 60);
+
+// How many updates there are between each redraw of the watched status.
+const WATCH_INTERVAL: u32 = 30;
+
+// How many updates there are between each status snapshot given to the view.
+const STATUS_INTERVAL: u32 = 6;
+
+// How many updates there are between each check for changes to the config.
+const RELOAD_INTERVAL: u32 = 60;
+
+/**
+ * The buttons that, when all held on any gamepad, immediately release every
+ * input on every slot. This is the gamepad equivalent of 'release_all'.
+ */
+const RELEASE_ALL_CHORD: [InputButton; 4] = [
+  InputButton::Start,
+  InputButton::Select,
+  InputButton::LeftBumper,
+  InputButton::RightBumper
+];
+
 /**
  * Represents a controller for an input client. The controller is ultimately
  * responsible for accepting user input (especially from gamepads), as well as
  * updating the model and view accordingly. This also means it's responsible for
  * mapping gamepads to individual slots.
+ *
+ * Slots whose gamepads were physically disconnected can be held, in which case
+ * they're kept in "held" (mapped from slot index to the last gamepad's ID and
+ * name, and when it was disconnected) until they're reclaimed or their hold
+ * timeout expires. Slots are reclaimed by name rather than ID, since some
+ * adapters give a gamepad a new ID whenever it's plugged back in. Names are
+ * kept in "device_names", since they can't be looked up once a gamepad is gone.
+ *
+ * The buttons currently held on each gamepad are also kept in "pressed", which
+ * is how button chords are detected. Unassigned gamepads that are holding an
+ * activation chord are kept in "activating", mapped to the index of the
+ * activation, when its chord was first held, and whether it was already tried,
+ * so a chord that couldn't connect isn't tried again until it's held again.
+ *
+ * Slots in "locked" refuse to be assigned to automatically, but gamepads can
+ * still be assigned to them by hand.
+ *
+ * When auto-assigning, the gamepads that were connected as of the last update
+ * are kept in "known_devices", so newly connected ones can be told apart. Ones
+ * that couldn't be assigned since every slot was taken are kept in
+ * "waiting_devices" too, so they're assigned once a slot frees up.
+ *
+ * While the status is being watched, "watch_ticks" counts the updates left
+ * until it's redrawn. Likewise, "status_ticks" counts the updates left until
+ * the view is given a new status snapshot.
+ *
+ * How well the client is keeping up, like how steadily packets are sent and
+ * how many events are read, is tracked in "stats".
+ *
+ * The scripts that are currently being run are kept in "sourcing", so a script
+ * can't end up running itself.
+ *
+ * Besides the view, commands can also come from other programs through
+ * "remote", which is where calls from the JSON-RPC server end up. Other
+ * programs can also listen to "events", which are published whenever a
+ * gamepad goes in or out of a slot, or whenever an error is reported. To tell
+ * when that happens, the gamepad in each slot as of the last update is kept in
+ * "slotted", mapped from slot index to gamepad ID.
+ *
+ * The config is loaded from and saved to "config_path". Any settings in
+ * "overrides" are applied on top of it whenever it's loaded, but are left out
+ * whenever it's saved.
+ *
+ * The config is reloaded whenever "reload_flag" is raised (like on SIGHUP), or
+ * whenever its modification time stops matching "config_modified", which is
+ * checked every time "reload_ticks" runs out.
+ *
+ * Likewise, the client exits whenever "exit_flag" is raised (like on SIGINT or
+ * SIGTERM), just as if 'exit' was run. Once the client has exited, the status
+ * it exited with is kept in "exit_code", which update() hands back so whoever
+ * is driving the controller can end the process.
+ *
+ * While sending to the server keeps failing, the "outage" is tracked so sends
+ * can be retried without giving up on every slot right away.
+ *
+ * Gamepads are cleaned up over several updates after the client is stopped.
+ * Commands that can't run until that's over, like 'start' and 'exit', wait in
+ * "queued" instead.
  */
 pub struct ClientController {
-  switch_pads: Vec<SwitchPad>,
-  input_delays: Vec<u8>,
+  config_path: PathBuf,
+  overrides: ConfigOverrides,
+  reload_flag: Arc<AtomicBool>,
+  config_modified: Option<time::SystemTime>,
+  reload_ticks: u32,
+  auto_assign: bool,
+  startup_script: Option<PathBuf>,
+  cleanup_ms: u64,
+  reconnect_ms: u64,
+  preferred_slots: HashMap<String, usize>,
+  mappings: HashMap<String, HashMap<String, SwitchButton>>,
+  activations: Vec<Activation>,
+  slots: Vec<SlotConfig>,
 
   model: ClientModel,
   view: Box<dyn ClientView>,
   running: bool,
+  outage: Option<Outage>,
+  exit_flag: Arc<AtomicBool>,
+  exit_code: Option<i32>,
+  queued: Option<Command>,
 
   input_adapter: Box<dyn InputAdapter>,
   input_map: HashMap<usize, usize>,
   input_buffer: Vec<(InputEvent, u8)>,
+  held: HashMap<usize, (usize, Option<String>, time::Instant)>,
+  device_names: HashMap<usize, String>,
+  pressed: HashMap<usize, HashSet<InputButton>>,
+  activating: HashMap<usize, (usize, time::Instant, bool)>,
+  locked: HashSet<usize>,
+  known_devices: HashSet<usize>,
+  waiting_devices: HashSet<usize>,
+  watch_ticks: Option<u32>,
+  status_ticks: u32,
+  stats: StatsTracker,
+  sourcing: Vec<PathBuf>,
+  remote: Option<Receiver<RemoteRequest>>,
+  events: EventBus,
+  slotted: HashMap<usize, usize>
 }
 
 impl ClientController {
   /**
    * Constructs a controller from a model and a view, as well as an input
    * adapter. The input adapter is especially important in reading inputs from
-   * supported gamepads.
+   * supported gamepads. The config will be loaded from the given path, with the
+   * given overrides applied.
    */
   pub fn new(
     model: ClientModel, view: Box<dyn ClientView>,
-    input_adapter: Box<dyn InputAdapter>
+    input_adapter: Box<dyn InputAdapter>, config_path: PathBuf,
+    overrides: ConfigOverrides
   ) -> ClientController {
     return ClientController {
-      switch_pads: vec!(),
-      input_delays: vec!(),
+      config_path: config_path,
+      overrides: overrides,
+      reload_flag: Arc::new(AtomicBool::new(false)),
+      config_modified: None,
+      reload_ticks: RELOAD_INTERVAL,
+      auto_assign: false,
+      startup_script: None,
+      cleanup_ms: 0,
+      reconnect_ms: 0,
+      preferred_slots: HashMap::new(),
+      mappings: HashMap::new(),
+      activations: vec!(),
+      slots: vec!(),
 
       model: model,
       view: view,
       running: false,
+      outage: None,
+      exit_flag: Arc::new(AtomicBool::new(false)),
+      exit_code: None,
+      queued: None,
 
       input_adapter: input_adapter,
       input_map: HashMap::new(),
-      input_buffer: vec!()
+      input_buffer: vec!(),
+      held: HashMap::new(),
+      device_names: HashMap::new(),
+      pressed: HashMap::new(),
+      activating: HashMap::new(),
+      locked: HashSet::new(),
+      known_devices: HashSet::new(),
+      waiting_devices: HashSet::new(),
+      watch_ticks: None,
+      status_ticks: 0,
+      stats: StatsTracker::new(UPDATE_INTERVAL),
+      sourcing: vec!(),
+      remote: None,
+      events: EventBus::new(),
+      slotted: HashMap::new()
     }
   } 
 
   /**
    * Setters, but these fields should only be set (outside of the controller) by
    * actually sending commands to the controller. Every time a setter is called,
-   * the current config is saved.
+   * the current config is saved, along with any override of the field.
    */
   fn set_server_ip(&mut self, server_ip: &String) -> Result<String, String> {
     self.model.set_server_ip(server_ip);
+    self.overrides.clear_server_ip();
     return self.save_config();
   }
 
-  fn set_switch_pad(
-    &mut self, i: &usize, switch_pad: &SwitchPad
-  ) -> Result<String, String> {
-    self.switch_pads[*i] = *switch_pad;
+  fn set_auto_assign(&mut self, auto_assign: &bool) -> Result<String, String> {
+    self.auto_assign = *auto_assign;
+    self.known_devices.clear();
+    self.waiting_devices.clear();
     return self.save_config();
   }
 
-  fn set_input_delay(
-    &mut self, i: &usize, input_delay: &u8
+  fn set_preferred_slot(
+    &mut self, gamepad_id: &usize, i: &Option<usize>
+  ) -> Result<String, String> {
+    return match self.device_name(gamepad_id) {
+      Some(name) => {
+        match i {
+          Some(i) => {
+            if let Err(e) = self.check_slot(i) {
+              return Err(e);
+            }
+            self.preferred_slots.insert(name, *i);
+          },
+          None => {
+            self.preferred_slots.remove(&name);
+          }
+        }
+        self.save_config()
+      },
+      None => Err(
+        format!("No gamepad with an id of {} is connected.", gamepad_id)
+      )
+    }
+  }
+
+  /**
+   * Sets one of a slot's settings from text, as long as the config is still
+   * valid afterwards. A slot's mapping and deadzones apply right away, but its
+   * controller type only applies once a gamepad is connected to it again.
+   */
+  fn set_slot(
+    &mut self, i: &usize, key: &str, value: &str
   ) -> Result<String, String> {
-    self.input_delays[*i] = *input_delay;
+    if let Err(e) = self.check_slot(i) {
+      return Err(e);
+    }
+    let mut slot: SlotConfig = self.slots[*i].clone();
+    if let Err(e) = slot.set(key, value) {
+      return Err(e);
+    }
+    let previous: SlotConfig = std::mem::replace(&mut self.slots[*i], slot);
+    let errors: Vec<String> =
+      self.current_config().validate(self.model.num_pads());
+    if !errors.is_empty() {
+      self.slots[*i] = previous;
+      return Err(errors.join("\n"));
+    }
+    match key {
+      "switch_pad" => self.overrides.clear_switch_pad(i),
+      "input_delay" => self.overrides.clear_input_delay(i),
+      _ => ()
+    }
+    self.configure_pads();
     return self.save_config();
   }
 
+  // Lists every setting of a slot.
+  fn show_slot(&self, i: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_slot(i) {
+      return Err(e);
+    }
+    let mut lines: Vec<String> = vec!();
+    for key in SLOT_KEYS {
+      match self.slots[*i].get(key) {
+        Ok(value) => lines.push(format!("{} = {}", key, value)),
+        Err(e) => return Err(e)
+      }
+    }
+    return Ok(format!("Slot {}:\n{}", i + 1, lines.join("\n")));
+  }
+
+  // Gives every emulated pad its slot's mapping and stick deadzones.
+  fn configure_pads(&mut self) -> () {
+    for i in 0..self.slots.len() {
+      let mapping: HashMap<InputButton, SwitchButton> =
+        match self.slots[i].get_mapping() {
+          Some(name) => match self.mappings.get(name) {
+            Some(mapping) => config::parse_mapping(mapping)
+              .unwrap_or_default(),
+            None => HashMap::new()
+          },
+          None => HashMap::new()
+        };
+      self.model.configure_pad(&i, mapping, self.slots[i].get_deadzones());
+    }
+  }
+
   /**
    * Initializes this controller with a greeting message, and by loading a
    * config.
@@ -103,22 +372,61 @@ impl ClientController {
           commands."
           .to_string()
         );
+        if let Some(path) = self.startup_script_path() {
+          let result: Result<String, String> = self.source(&path);
+          self.report(result);
+        }
         return Ok(());
       },
       Err(e) => Err(e)
     }
   }
 
-  // Loads a config, which is expected to be in the current directory.
+  /**
+   * Loads the config and applies the overrides to it. A default config is
+   * created if there isn't one yet.
+   */
   fn load_config(&mut self) -> Result<String, String> {
-    let confy_load: Result<Config, ConfyError> =
-      confy::load_path("./config.toml");
-    return match confy_load {
-      Ok(config) => {
+    if let Some(dir) = self.config_path.parent() {
+      if let Err(e) = fs::create_dir_all(dir) {
+        return Err(format!("Error occurred while loading config: {}", e));
+      }
+    }
+    return match config::load(&self.config_path, self.model.num_pads()) {
+      Ok((config, migrated)) => {
+        if let Some(msg) = migrated {
+          self.view.writeln(msg);
+        }
+        let config: Config = match self.overrides.apply(config) {
+          Ok(config) => config,
+          Err(e) => return Err(
+            format!("Error occurred while overriding config: {}", e)
+          )
+        };
         self.model.set_server_ip(config.get_server_ip());
-        self.switch_pads = config.get_switch_pads().clone();
-        self.input_delays = config.get_input_delays().clone();
-        return Ok("Config successfully loaded.".to_string());
+        self.model.set_server_port(config.get_server_port());
+        self.auto_assign = *config.get_auto_assign();
+        self.startup_script = config.get_startup_script().clone();
+        self.cleanup_ms = *config.get_cleanup_ms();
+        self.reconnect_ms = *config.get_reconnect_ms();
+        self.preferred_slots = config.get_preferred_slots().clone();
+        self.mappings = config.get_mappings().clone();
+        self.activations = config.get_activations().clone();
+        self.slots = config.get_slots().clone();
+        self.configure_pads();
+        self.config_modified = self.modified_time();
+        info!("Loaded config from {}.", self.config_path.display());
+        if self.overrides.is_empty() {
+          return Ok("Config successfully loaded.".to_string());
+        } else {
+          return Ok(
+            format!(
+              "Config successfully loaded, with {} setting(s) overridden. \
+              These won't be saved unless they're changed with a command.",
+              self.overrides.len()
+            )
+          );
+        }
       },
       Err(e) => Err(
         format!("Error occurred while loading config: {}", e)
@@ -126,23 +434,301 @@ impl ClientController {
     }
   } 
 
-  // Saves the current config to the current directory.
-  fn save_config(&self) -> Result<String, String> {
-    return match confy::store_path("./config.toml", self.current_config())  {
-      Ok(_) => Ok("Config successfully saved.".to_string()),
+  /**
+   * Saves the current config. Overridden settings keep whatever values are
+   * already saved.
+   */
+  fn save_config(&mut self) -> Result<String, String> {
+    let mut config: Config = self.current_config();
+    if !self.overrides.is_empty() {
+      let confy_load: Result<Config, ConfyError> =
+        confy::load_path(&self.config_path);
+      config = match confy_load {
+        Ok(saved) => self.overrides.restore(config, &saved),
+        Err(_) => self.overrides.restore(config, &Config::default())
+      };
+    }
+    return match confy::store_path(&self.config_path, config)  {
+      Ok(_) => {
+        self.config_modified = self.modified_time();
+        Ok("Config successfully saved.".to_string())
+      },
       Err(e) => Err(
         format!("Error occurred while saving config: {}", e)
       )
     }
   }
 
+  // Returns when the config was last modified, if it can be told.
+  fn modified_time(&self) -> Option<time::SystemTime> {
+    return fs::metadata(&self.config_path)
+      .and_then(|metadata| metadata.modified())
+      .ok();
+  }
+
+  /**
+   * Returns a flag that makes the controller reload the config on its next
+   * update once raised. This is how signals like SIGHUP reach the controller.
+   */
+  pub fn get_reload_flag(&self) -> Arc<AtomicBool> {
+    return self.reload_flag.clone();
+  }
+
+  /**
+   * Reloads the config if the reload flag was raised, or if the config was
+   * modified since it was last loaded or saved.
+   */
+  fn update_reload(&mut self) -> () {
+    let mut reload: bool = self.reload_flag.swap(false, Ordering::Relaxed);
+    if self.reload_ticks == 0 {
+      let modified: Option<time::SystemTime> = self.modified_time();
+      if modified.is_some() && modified != self.config_modified {
+        reload = true;
+      }
+      self.reload_ticks = RELOAD_INTERVAL;
+    } else {
+      self.reload_ticks -= 1;
+    }
+    if reload {
+      let result: Result<String, String> = self.reload_config();
+      self.report(result);
+    }
+  }
+
+  /**
+   * Reloads the config while the client keeps running, with the overrides
+   * applied on top of it again. Every change applies right away, and a slot
+   * whose controller type changed is reconnected as its new type. If the config
+   * is invalid, the current settings are kept instead.
+   */
+  fn reload_config(&mut self) -> Result<String, String> {
+    self.config_modified = self.modified_time();
+    let config: Config =
+      match config::load(&self.config_path, self.model.num_pads()) {
+        Ok((config, migrated)) => {
+          if let Some(msg) = migrated {
+            self.view.writeln(msg);
+          }
+          match self.overrides.apply(config) {
+            Ok(config) => config,
+            Err(e) => return Err(format!("Config wasn't reloaded: {}", e))
+          }
+        },
+        Err(e) => return Err(format!("Config wasn't reloaded: {}", e))
+      };
+    let mut applied: Vec<String> = vec!();
+    let mut retyped: Vec<usize> = vec!();
+    if config.get_server_ip() != self.model.get_server_ip() {
+      self.model.set_server_ip(config.get_server_ip());
+      applied.push("server_ip".to_string());
+    }
+    if config.get_server_port() != self.model.get_server_port() {
+      self.model.set_server_port(config.get_server_port());
+      applied.push("server_port".to_string());
+    }
+    if *config.get_auto_assign() != self.auto_assign {
+      self.auto_assign = *config.get_auto_assign();
+      self.known_devices.clear();
+      self.waiting_devices.clear();
+      applied.push("auto_assign".to_string());
+    }
+    if *config.get_startup_script() != self.startup_script {
+      self.startup_script = config.get_startup_script().clone();
+      applied.push("startup_script".to_string());
+    }
+    if *config.get_cleanup_ms() != self.cleanup_ms {
+      self.cleanup_ms = *config.get_cleanup_ms();
+      applied.push("cleanup_ms".to_string());
+    }
+    if *config.get_reconnect_ms() != self.reconnect_ms {
+      self.reconnect_ms = *config.get_reconnect_ms();
+      applied.push("reconnect_ms".to_string());
+    }
+    if *config.get_preferred_slots() != self.preferred_slots {
+      self.preferred_slots = config.get_preferred_slots().clone();
+      applied.push("preferred_slots".to_string());
+    }
+    if *config.get_mappings() != self.mappings {
+      self.mappings = config.get_mappings().clone();
+      applied.push("mappings".to_string());
+    }
+    if *config.get_activations() != self.activations {
+      self.activations = config.get_activations().clone();
+      applied.push("activations".to_string());
+    }
+    for (i, slot) in config.get_slots().iter().enumerate() {
+      for key in SLOT_KEYS {
+        if slot.get(key).ok() != self.slots[i].get(key).ok() {
+          applied.push(format!("slot {} {}", i + 1, key));
+          if key == "switch_pad" {
+            retyped.push(i);
+          }
+        }
+      }
+    }
+    self.slots = config.get_slots().clone();
+    self.configure_pads();
+    for i in retyped {
+      self.retype_slot(&i);
+    }
+
+    if applied.is_empty() {
+      return Ok("Config reloaded, but nothing changed.".to_string());
+    }
+    return Ok(format!("Config reloaded. Applied: {}.", applied.join(", ")));
+  }
+
+  /**
+   * Reconnects a slot that's in use as the controller type it's now set to. If
+   * it's now set to Disconnected, whatever gamepad was in it is disconnected
+   * instead, and a held slot is released.
+   */
+  fn retype_slot(&mut self, i: &usize) -> () {
+    let gamepad_id: Option<usize> = self.gamepad_at(i);
+    if gamepad_id.is_none() && !self.held.contains_key(i) {
+      return;
+    }
+    let switch_pad: SwitchPad = *self.slots[*i].get_switch_pad();
+    self.model.disconnect_pad(i);
+    if switch_pad == SwitchPad::Disconnected {
+      if let Some(gamepad_id) = gamepad_id {
+        self.input_map.remove(&gamepad_id);
+      }
+      self.held.remove(i);
+    } else {
+      self.model.connect_pad(i, &switch_pad);
+    }
+  }
+
   // Returns the current config as an effective clone.
   fn current_config(&self) -> Config {
     return Config::new(
       self.model.get_server_ip().to_string(),
-      self.switch_pads.clone(),
-      self.input_delays.clone()
+      *self.model.get_server_port(),
+      self.auto_assign,
+      self.startup_script.clone(),
+      self.cleanup_ms,
+      self.reconnect_ms,
+      self.preferred_slots.clone(),
+      self.mappings.clone(),
+      self.activations.clone(),
+      self.slots.clone()
+    );
+  }
+
+  // Saves the current server and slot settings as a profile.
+  fn save_profile(&self, name: &str) -> Result<String, String> {
+    let path: PathBuf = match config::profile_path(&self.config_path, name) {
+      Ok(path) => path,
+      Err(e) => return Err(e)
+    };
+    if let Some(dir) = path.parent() {
+      if let Err(e) = fs::create_dir_all(dir) {
+        return Err(format!("Error occurred while saving profile: {}", e));
+      }
+    }
+    let profile: Profile = Profile::new(
+      self.model.get_server_ip().to_string(),
+      *self.model.get_server_port(),
+      self.mappings.clone(),
+      self.slots.clone()
+    );
+    return match confy::store_path(&path, profile) {
+      Ok(_) => Ok(format!("Profile '{}' successfully saved.", name)),
+      Err(e) => Err(format!("Error occurred while saving profile: {}", e))
+    }
+  }
+
+  /**
+   * Loads a profile's server and slot settings, and saves them to the config.
+   * Any settings given on the command line are replaced as well, and slots in
+   * use are reconnected if their controller type changed.
+   */
+  fn load_profile(&mut self, name: &str) -> Result<String, String> {
+    let path: PathBuf = match config::profile_path(&self.config_path, name) {
+      Ok(path) => path,
+      Err(e) => return Err(e)
+    };
+    if !path.exists() {
+      return Err(format!("There's no profile named '{}'.", name));
+    }
+    let confy_load: Result<Profile, ConfyError> = confy::load_path(&path);
+    let profile: Profile = match confy_load {
+      Ok(profile) => profile,
+      Err(e) => return Err(
+        format!("Error occurred while loading profile: {}", e)
+      )
+    };
+    let config: Config = Config::new(
+      profile.get_server_ip().to_string(),
+      *profile.get_server_port(),
+      self.auto_assign,
+      self.startup_script.clone(),
+      self.cleanup_ms,
+      self.reconnect_ms,
+      self.preferred_slots.clone(),
+      profile.get_mappings().clone(),
+      self.activations.clone(),
+      profile.get_slots().clone()
     );
+    let errors: Vec<String> = config.validate(self.model.num_pads());
+    if !errors.is_empty() {
+      return Err(
+        format!(
+          "Profile '{}' has the following problems:\n{}",
+          name,
+          c![format!("- {}", error), for error in errors].join("\n")
+        )
+      );
+    }
+    self.model.set_server_ip(profile.get_server_ip());
+    self.model.set_server_port(profile.get_server_port());
+    self.mappings = profile.get_mappings().clone();
+    let retyped: Vec<usize> = c![
+      i,
+      for i in 0..self.slots.len(),
+      if profile.get_slots()[i].get_switch_pad()
+        != self.slots[i].get_switch_pad()
+    ];
+    self.slots = profile.get_slots().clone();
+    self.configure_pads();
+    for i in retyped {
+      self.retype_slot(&i);
+    }
+    self.overrides.clear();
+    return match self.save_config() {
+      Ok(_) => Ok(format!("Profile '{}' successfully loaded.", name)),
+      Err(e) => Err(e)
+    }
+  }
+
+  // Lists the names of every saved profile.
+  fn list_profiles(&self) -> Result<String, String> {
+    return match config::list_profiles(&self.config_path) {
+      Ok(names) => {
+        if names.is_empty() {
+          Ok("No profiles have been saved.".to_string())
+        } else {
+          Ok(format!("Saved profiles:\n{}", names.join("\n")))
+        }
+      },
+      Err(e) => Err(format!("Error occurred while listing profiles: {}", e))
+    }
+  }
+
+  // Deletes a saved profile.
+  fn delete_profile(&self, name: &str) -> Result<String, String> {
+    let path: PathBuf = match config::profile_path(&self.config_path, name) {
+      Ok(path) => path,
+      Err(e) => return Err(e)
+    };
+    if !path.exists() {
+      return Err(format!("There's no profile named '{}'.", name));
+    }
+    return match fs::remove_file(&path) {
+      Ok(_) => Ok(format!("Profile '{}' successfully deleted.", name)),
+      Err(e) => Err(format!("Error occurred while deleting profile: {}", e))
+    }
   }
 
   // Restarts the client, but only if it's currently running.
@@ -173,139 +759,629 @@ impl ClientController {
     } else {
       if self.running {
         return Err("The client is already running.".to_string());
+      } else if self.model.is_cleaning_up() {
+        if self.queued == Some(Command::Exit) {
+          return Err(
+            "The client is about to exit, so it can't be started.".to_string()
+          );
+        }
+        self.queued = Some(Command::Start);
+        return Ok(
+          "The client will start once it's done cleaning up.".to_string()
+        );
       } else {
         self.running = true;
+        self.model.reset_packet_counts();
+        self.stats.reset();
+        info!(
+          "Started sending inputs to {}:{}.",
+          self.model.get_server_ip(), self.model.get_server_port()
+        );
         return Ok("The client is ready to receive inputs.".to_string());
       }
     }
   }
 
-  // Stops the client if it's currently running.
+  /**
+   * Stops the client if it's currently running. If it's only cleaning up, this
+   * instead keeps it from starting afterwards, if it was going to.
+   */
   fn stop(&mut self) -> Result<String, String> {
     if self.running {
       self.running = false;
-      match self.cleanup() {
-        Ok(_) => return Ok("The client has been stopped.".to_string()),
-        Err(e) => return Err(e)
-      }
+      self.outage = None;
+      info!("Stopped sending inputs.");
+      self.cleanup();
+      return Ok(
+        format!(
+          "The client has been stopped. Cleaning up connected gamepads... {}",
+          self.cleanup_length()
+        )
+      );
+    } else if self.queued == Some(Command::Start) {
+      self.queued = None;
+      return Ok(
+        "The client won't start once it's done cleaning up after all."
+        .to_string()
+      );
     } else {
       return Err("The client isn't running.".to_string());
     }
   }
 
-  // Cleans up and disconnects all connected gamepads.
-  fn cleanup(&mut self) -> Result<String, String> {
-    self.view.writeln(
-      "Cleaning up connected gamepads... This will take a moment.".to_string()
-    );
+  /**
+   * Starts cleaning up and disconnecting all connected gamepads, which carries
+   * on over the next updates.
+   */
+  fn cleanup(&mut self) -> () {
     self.input_map.clear();
-    return self.model.cleanup();
+    self.held.clear();
+    self.device_names.clear();
+    self.pressed.clear();
+    self.activating.clear();
+    self.known_devices.clear();
+    self.waiting_devices.clear();
+    self.input_buffer.clear();
+    self.model.start_cleanup(time::Duration::from_millis(self.cleanup_ms));
   }
 
-  // Exits the client, which is effectively stopping then process::exit().
-  fn exit(&mut self) -> Result<String, String> {
+  // Returns how long a cleanup takes, worded for users.
+  fn cleanup_length(&self) -> String {
+    if self.cleanup_ms == 0 {
+      return "This will be done right away.".to_string();
+    } else if self.cleanup_ms < 1000 {
+      return format!("This will take {} millisecond(s).", self.cleanup_ms);
+    } else {
+      return format!(
+        "This will take {:.1} second(s).", self.cleanup_ms as f32 / 1000.0
+      );
+    }
+  }
+
+  /**
+   * Carries on with the cleanup, if one's underway. Once it's over, whatever
+   * command was queued up behind it is run.
+   */
+  fn update_cleanup(&mut self) -> () {
+    match self.model.update_cleanup() {
+      Ok(true) => {
+        self.report(Ok("Gamepads should now be cleaned up.".to_string()));
+        if let Some(command) = self.queued.take() {
+          let result: Result<String, String> = self.run(command);
+          self.report(result);
+        }
+      },
+      Ok(false) => (),
+      Err(e) => {
+        if self.queued.take() == Some(Command::Exit) {
+          let result: Result<String, String> = self.exit_err(e);
+          self.report(result);
+        } else {
+          self.report(Err(e));
+        }
+      }
+    }
+  }
+
+  /**
+   * Releases every input on every slot and immediately sends the neutral state
+   * to the input server, like any other update would. Any inputs still waiting
+   * on input delay are dropped, so they can't press anything again afterwards.
+   */
+  fn release_all(&mut self) -> Result<String, String> {
+    self.input_buffer.clear();
+    self.model.neutralize_all();
     if self.running {
+      if let Err(e) = self.update_server() {
+        return Err(e);
+      }
+    }
+    return Ok("Released all inputs on every slot.".to_string());
+  }
+
+  /**
+   * Returns a flag that makes the controller exit on its next update once
+   * raised. This is how signals like SIGINT and SIGTERM reach the controller.
+   */
+  pub fn get_exit_flag(&self) -> Arc<AtomicBool> {
+    return self.exit_flag.clone();
+  }
+
+  /**
+   * Exits the client if the exit flag was raised. The flag is never lowered,
+   * so whatever raised it can tell the client is already exiting.
+   */
+  fn update_exit(&mut self) -> () {
+    if self.exit_flag.load(Ordering::Relaxed)
+      && self.queued != Some(Command::Exit) {
+      info!("Exiting because of a signal.");
+      let result: Result<String, String> = self.exit();
+      self.report(result);
+    }
+  }
+
+  /**
+   * Exits the client, which is effectively stopping then ending the process on
+   * the next update() returning. If the client has to clean up first, it only
+   * exits once that's done.
+   */
+  fn exit(&mut self) -> Result<String, String> {
+    if self.exit_code.is_some() {
+      return Ok("The client is already exiting.".to_string());
+    } else if self.queued == Some(Command::Exit) {
+      return Ok(
+        "The client will already exit once it's done cleaning up."
+        .to_string()
+      );
+    } else if self.running {
       return match self.stop() {
-        Ok(_) => self.exit_ok(),
+        Ok(msg) => {
+          self.queued = Some(Command::Exit);
+          Ok(format!("{} The client will exit once that's done.", msg))
+        },
         Err(e) => self.exit_err(e)
       }
+    } else if self.model.is_cleaning_up() {
+      self.queued = Some(Command::Exit);
+      return Ok(
+        "The client will exit once it's done cleaning up.".to_string()
+      );
     } else {
       return self.exit_ok();
     }
   }
 
+  /**
+   * Closes the view without exiting, giving back anything it took over. This is
+   * for when the client can't carry on at all, like when its config is invalid.
+   */
+  pub fn close(&mut self) -> () {
+    self.view.close();
+  }
+
   // Generically exits the client as a success.
   fn exit_ok(&mut self) -> Result<String, String> {
-    self.view.writeln("The client exited successfully. Goodbye!".to_string());
-    process::exit(0);
+    info!("Exiting.");
+    self.exit_code = Some(0);
+    return Ok("The client exited successfully. Goodbye!".to_string());
   }
 
   // Exits the client with an associated error message.
   fn exit_err(&mut self, e: String) -> Result<String, String> {
-    self.view.writeln(
+    error!("Exiting after an error: {}", e);
+    self.exit_code = Some(1);
+    return Err(
       format!(
         "The client did not exit successfully. The following error occurred: \
         {}", e
       )
     );
-    process::exit(1);
   }
 
   /**
    * Updates this controller, which then updates the model and view accordingly.
    * This is also where input events are received and parsed.
    *
-   * This should be used at a fixed time interval.
+   * This should be used at a fixed time interval. Once the client has exited,
+   * its view is closed and this returns the status to end the process with,
+   * which is 0 if it exited successfully and 1 otherwise.
    */
-  pub fn update(&mut self) -> () {
+  pub fn update(&mut self) -> Option<i32> {
+    if self.exit_code.is_some() {
+      return self.exit_code;
+    }
+    self.update_exit();
     match self.view.update() {
       Ok(_) => (),
-      Err(e) => {
+      // The view may keep failing while cleaning up, so this is only said once.
+      Err(e) if self.queued != Some(Command::Exit) => {
         self.view.writeln(
           format!(
             "The following error occurred while updating the view: {}", e
           )
         );
-        match self.exit() {
-          Ok(_) => (),
-          Err(_) => ()
-        }
-      }
+        let result: Result<String, String> = self.exit();
+        self.report(result);
+      },
+      Err(_) => ()
     }
     self.parse_command_buffer();
 
     if self.running { 
       self.update_inputs();
       if let Err(e) = self.update_server() {
-        self.view.writeln(format!("{}", e));
+        self.report(Err(e));
       }
+    } else {
+      self.update_cleanup();
+    }
+    self.update_events();
+    self.update_reload();
+    self.update_remote();
+    self.update_watch();
+    self.update_view_status();
+    if self.exit_code.is_some() {
+      self.view.close();
+    }
+    return self.exit_code;
+  }
+
+  // Gives the view a new status snapshot if it's time to do so.
+  fn update_view_status(&mut self) -> () {
+    if self.status_ticks == 0 {
+      let status: ClientStatus = self.status();
+      self.view.update_status(&status);
+      self.status_ticks = STATUS_INTERVAL;
+    } else {
+      self.status_ticks -= 1;
+    }
+  }
+
+  // Redraws the status if it's being watched and it's time to do so.
+  fn update_watch(&mut self) -> () {
+    match self.watch_ticks {
+      Some(0) => {
+        let status: ClientStatus = self.status();
+        self.view.watch_status(&status);
+        self.watch_ticks = Some(WATCH_INTERVAL);
+      },
+      Some(ticks) => self.watch_ticks = Some(ticks - 1),
+      None => ()
     }
   }
 
   /**
-   * Tells the model to update the input server. If an issue occurs while doing
-   * so, the client will attempt to stop and cleanup immediately.
+   * Accepts calls made from other programs, which are handled in between
+   * commands from the view.
    */
-  fn update_server(&mut self) -> Result<(), String> {
-    let mut errors: Vec<String> = vec!();
-    match self.model.update_server() {
-      Ok(msg) => return Ok(msg),
-      Err(e) => {
-        errors.push(e);
-        if let Err(e_stop) = self.stop() {
-          errors.push(e_stop);
-        }
-        return Err(
-          format!(
-            "Received the following errors while attempting to update and \
-            cleanup the server: {:?}",
-            errors
-          )
+  pub fn listen(&mut self, remote: Receiver<RemoteRequest>) -> () {
+    self.remote = Some(remote);
+  }
+
+  /**
+   * Answers every call that was made from another program since the last
+   * update. The results of commands are also written to the view, as if they
+   * were typed into it.
+   */
+  fn update_remote(&mut self) -> () {
+    let requests: Vec<RemoteRequest> = match &self.remote {
+      Some(remote) => remote.try_iter().collect(),
+      None => return
+    };
+    for request in requests {
+      debug!("Received remote call {:?}.", request.get_call());
+      let response: Result<RemoteResponse, String> =
+        match request.get_call().clone() {
+          RemoteCall::Run(command) => {
+            let result: Result<String, String> = self.run(command);
+            self.report(result.clone());
+            result.map(RemoteResponse::Message)
+          },
+          RemoteCall::Status => Ok(RemoteResponse::Status(self.status())),
+          RemoteCall::Config =>
+            Ok(RemoteResponse::Config(self.current_config()))
+        };
+      request.respond(response);
+    }
+  }
+
+  // Returns the events that this controller publishes to.
+  pub fn get_events(&self) -> EventBus {
+    return self.events.clone();
+  }
+
+  /**
+   * Publishes an event for every gamepad that went in or out of a slot since
+   * the last update, no matter how it happened.
+   */
+  fn update_events(&mut self) -> () {
+    let slotted: HashMap<usize, usize> = self.input_map
+      .iter()
+      .map(|(gamepad_id, i)| (*i, *gamepad_id))
+      .collect();
+    if slotted == self.slotted {
+      return;
+    }
+    for (i, gamepad_id) in &self.slotted {
+      if slotted.get(i) != Some(gamepad_id) {
+        info!("Gamepad {} left slot {}.", gamepad_id, i + 1);
+        self.events.publish(
+          ClientEvent::Disconnected {
+            slot: *i,
+            gamepad_id: *gamepad_id,
+            held: self.held.contains_key(i)
+          }
+        );
+      }
+    }
+    let devices: HashMap<usize, String> = self.input_adapter
+      .get_devices()
+      .into_iter()
+      .collect();
+    for (i, gamepad_id) in &slotted {
+      if self.slotted.get(i) != Some(gamepad_id) {
+        let name: String = devices.get(gamepad_id)
+          .cloned()
+          .unwrap_or("Unknown".to_string());
+        info!("Gamepad {} ({}) joined slot {}.", gamepad_id, name, i + 1);
+        self.events.publish(
+          ClientEvent::Connected {
+            slot: *i,
+            gamepad_id: *gamepad_id,
+            name: name
+          }
         );
       }
     }
+    self.slotted = slotted;
   }
 
-  // Update everything related to inputs on this controller.
-  fn update_inputs(&mut self) -> () {
-    self.disconnect_inactive();
-    self.fill_input_buffer();
-    self.parse_input_buffer();
-  } 
-
-  // Disconnects all disconnected controllers from this controller.
-  fn disconnect_inactive(&mut self) -> () {
-    for (gamepad_id, _) in self.input_map.clone() {
-      if !self.input_adapter.is_connected(&gamepad_id) {
-        match self.disconnect(&gamepad_id) {
-          Ok(msg) => self.view.writeln(format!("{}", msg)),
-          Err(e) => self.view.writeln(format!("{}", e))
-        }
+  /**
+   * Writes the result of something to the view. Errors are also published as
+   * events, so other programs can find out about them. Either way, the result
+   * is logged too.
+   */
+  pub fn report(&mut self, result: Result<String, String>) -> () {
+    match result {
+      Ok(msg) => {
+        debug!("{}", msg);
+        self.view.writeln(msg);
+      },
+      Err(e) => {
+        warn!("{}", e);
+        self.events.publish(ClientEvent::Error(e.clone()));
+        self.view.writeln(e);
       }
     }
   }
 
-  // Disconnects the gamepad with the given ID, if it exists.
+  // Returns a snapshot of this controller's current state.
+  fn status(&mut self) -> ClientStatus {
+    let devices: HashMap<usize, String> = self.input_adapter
+      .get_devices()
+      .into_iter()
+      .collect();
+    let mut slots: Vec<SlotStatus> = vec!();
+    for i in 0..self.model.num_pads() {
+      let gamepad: Option<(usize, String)> = self.gamepad_at(&i)
+        .map(|gamepad_id| (
+          gamepad_id,
+          devices.get(&gamepad_id).cloned().unwrap_or("Unknown".to_string())
+        ));
+      let pad: &EmulatedPad = self.model.get_pad(&i);
+      slots.push(
+        SlotStatus::new(
+          self.slots[i].get_nickname().clone(),
+          *pad.get_switch_pad(),
+          *self.slots[i].get_input_delay(),
+          gamepad,
+          self.held.contains_key(&i),
+          self.locked.contains(&i),
+          *pad.get_keyout(),
+          *pad.get_left(),
+          *pad.get_right()
+        )
+      );
+    }
+    return ClientStatus::new(
+      self.running,
+      self.outage.is_some(),
+      self.model.get_server_ip().to_string(),
+      *self.model.get_packets_sent(),
+      self.model.get_cleanup_progress(),
+      slots,
+      self.stats_snapshot()
+    );
+  }
+
+  // Returns a snapshot of how well this controller is keeping up.
+  fn stats_snapshot(&mut self) -> ClientStats {
+    return self.stats.snapshot(
+      self.input_adapter.get_name().to_string(),
+      self.input_adapter.get_devices().len(),
+      *self.model.get_packets_sent(),
+      *self.model.get_packets_failed(),
+      *self.input_adapter.get_dropped(),
+      self.input_buffer.len()
+    );
+  }
+
+  // Shows the current status once, or starts watching it.
+  fn show_status(&mut self, watch: bool) -> Result<String, String> {
+    if watch {
+      self.watch_ticks = Some(0);
+      return Ok("Watching status...".to_string());
+    } else {
+      return Ok(format!("{}", self.status()));
+    }
+  }
+
+  /**
+   * Tells the model to update the input server. If an issue occurs while doing
+   * so, sends are retried with backoff while every slot is kept as it is. Only
+   * the first error is returned, unless the client gives up on the server, in
+   * which case it stops and cleans up.
+   */
+  fn update_server(&mut self) -> Result<(), String> {
+    if let Some(outage) = &self.outage {
+      if !outage.is_due() {
+        return Ok(());
+      }
+    }
+    match self.model.update_server() {
+      Ok(_) => {
+        self.stats.record_send();
+        if let Some(outage) = self.outage.take() {
+          self.report(
+            Ok(
+              format!(
+                "Reached the server again after {:.1} second(s) and {} failed \
+                send(s).",
+                outage.elapsed().as_secs_f32(), outage.get_failures()
+              )
+            )
+          );
+        }
+        return Ok(());
+      },
+      Err(e) => {
+        let window: time::Duration =
+          time::Duration::from_millis(self.reconnect_ms);
+        let first: bool = self.outage.is_none();
+        let outage: &mut Outage =
+          self.outage.get_or_insert_with(|| Outage::new(window));
+        outage.fail();
+        if outage.is_over() {
+          let failures: u32 = *outage.get_failures();
+          let stopped: String = match self.stop() {
+            Ok(msg) => msg,
+            Err(e_stop) => e_stop
+          };
+          return Err(
+            format!(
+              "Gave up on the server after {} failed send(s). {} {}",
+              failures, e, stopped
+            )
+          );
+        } else if first {
+          return Err(
+            format!(
+              "{} Retrying for up to {:.1} second(s) while keeping every slot.",
+              e, window.as_secs_f32()
+            )
+          );
+        } else {
+          debug!("Still can't reach the server: {}", e);
+          return Ok(());
+        }
+      }
+    }
+  }
+
+  // Update everything related to inputs on this controller.
+  fn update_inputs(&mut self) -> () {
+    self.disconnect_inactive();
+    self.release_expired_holds();
+    if self.auto_assign {
+      self.assign_new_devices();
+    }
+    self.fill_input_buffer();
+    self.update_device_names();
+    self.stats.record_queue(self.input_buffer.len());
+    self.parse_input_buffer();
+    self.activate_pending();
+  } 
+
+  /**
+   * Disconnects all disconnected controllers from this controller. If the slot
+   * a controller occupied has a hold timeout, the slot is held instead.
+   */
+  fn disconnect_inactive(&mut self) -> () {
+    for gamepad_id in self.pressed.keys().cloned().collect::<Vec<usize>>() {
+      if !self.input_adapter.is_connected(&gamepad_id) {
+        self.pressed.remove(&gamepad_id);
+        self.activating.remove(&gamepad_id);
+      }
+    }
+    for (gamepad_id, i) in self.input_map.clone() {
+      if !self.input_adapter.is_connected(&gamepad_id) {
+        let result: Result<String, String> =
+          if *self.slots[i].get_hold_timeout() > 0 {
+          self.hold(&gamepad_id)
+        } else {
+          self.disconnect(&gamepad_id)
+        };
+        self.report(result);
+      }
+    }
+  }
+
+  /**
+   * Remembers the name of every connected gamepad. A name is only forgotten
+   * once its gamepad is both disconnected and out of its slot.
+   */
+  fn update_device_names(&mut self) -> () {
+    let devices: Vec<(usize, String)> = self.input_adapter.get_devices();
+    let connected: HashSet<usize> = devices
+      .iter()
+      .map(|(gamepad_id, _)| *gamepad_id)
+      .collect();
+    let input_map: &HashMap<usize, usize> = &self.input_map;
+    self.device_names.retain(|gamepad_id, _| {
+      connected.contains(gamepad_id) || input_map.contains_key(gamepad_id)
+    });
+    self.device_names.extend(devices);
+  }
+
+  /**
+   * Unmaps the gamepad with the given ID, but keeps its slot connected on the
+   * Switch with neutral inputs. The slot can then be reclaimed by the same or
+   * another gamepad until its hold timeout expires.
+   */
+  fn hold(&mut self, gamepad_id: &usize) -> Result<String, String> {
+    if self.input_map.contains_key(gamepad_id) {
+      let i: usize = *self.input_map.get(gamepad_id).unwrap();
+      self.input_map.remove(gamepad_id);
+      self.model.neutralize_pad(&i);
+      let name: Option<String> = self.device_names.remove(gamepad_id);
+      self.held.insert(i, (*gamepad_id, name, time::Instant::now()));
+      return Ok(
+        format!(
+          "Gamepad (id: {}) was disconnected. Holding slot {} for {} seconds.",
+          gamepad_id,
+          i + 1,
+          self.slots[i].get_hold_timeout()
+        )
+      );
+    } else {
+      return Err(
+        format!(
+          "No gamepad with an id of {} is connected.",
+          gamepad_id
+        )
+      );
+    }
+  }
+
+  // Disconnects all held slots whose hold timeouts have expired.
+  fn release_expired_holds(&mut self) -> () {
+    for (i, (_, _, since)) in self.held.clone() {
+      if since.elapsed().as_secs() >= *self.slots[i].get_hold_timeout() as u64 {
+        self.release_held(&i);
+        self.report(
+          Ok(
+            format!(
+              "Slot {} was not reclaimed in time and was released.", i + 1
+            )
+          )
+        );
+      }
+    }
+  }
+
+  /**
+   * Disconnects a held slot, and lets other programs know that its gamepad is
+   * gone for good. Returns whether the slot was actually held.
+   */
+  fn release_held(&mut self, i: &usize) -> bool {
+    return match self.held.remove(i) {
+      Some((gamepad_id, _, _)) => {
+        self.model.disconnect_pad(i);
+        info!("Released held slot {}.", i + 1);
+        self.events.publish(
+          ClientEvent::Disconnected {
+            slot: *i,
+            gamepad_id: gamepad_id,
+            held: false
+          }
+        );
+        true
+      },
+      None => false
+    }
+  }
+
+  // Disconnects the gamepad with the given ID, if it exists.
   fn disconnect(&mut self, gamepad_id: &usize) -> Result<String, String> {
     if self.input_map.contains_key(gamepad_id) {
       let i: usize = *self.input_map.get(gamepad_id).unwrap();
@@ -328,36 +1404,64 @@ impl ClientController {
     }
   }
 
-  // Fills the input buffer with events from the input adapter.
+  /**
+   * Fills the input buffer with events from the input adapter.
+   *
+   * Since this sees events before any input delay is applied, this is also
+   * where held buttons are tracked and the release all chord is checked.
+   */
   fn fill_input_buffer(&mut self) -> () {
-    for event in self.input_adapter.read() {
+    let events: Vec<InputEvent> = self.input_adapter.read();
+    self.stats.record_events(events.len());
+    for event in events {
+      trace!(target: "client_rs::input", "{:?}", event);
+      let completes_chord: bool = self.track_pressed(&event);
       if let Some(i) = self.input_map.get(event.get_gamepad_id()) {
         self.input_buffer.insert(
           0,
-          (event, self.input_delays[*i]),
+          (event, *self.slots[*i].get_input_delay()),
         );
       } else {
         self.input_buffer.insert(0, (event, 0));
       }
+      if completes_chord {
+        let result: Result<String, String> = self.release_all();
+        self.report(result);
+      }
     }
   }
 
-  // Parses events from the input buffer and updates all corresponding gamepads.
+  /**
+   * Updates the buttons held on the gamepad an event came from. Returns whether
+   * the event completed the release all chord.
+   */
+  fn track_pressed(&mut self, event: &InputEvent) -> bool {
+    if let InputEvent::GamepadButton(gamepad_id, button, value) = event {
+      let pressed: &mut HashSet<InputButton> = self.pressed
+        .entry(*gamepad_id)
+        .or_default();
+      if *value != 0.0 {
+        pressed.insert(*button);
+        return RELEASE_ALL_CHORD.contains(button)
+          && RELEASE_ALL_CHORD.iter().all(|chord| pressed.contains(chord));
+      } else {
+        pressed.remove(button);
+      }
+    }
+    return false;
+  }
+
+  /**
+   * Parses events from the input buffer and updates all corresponding gamepads.
+   * Events from unassigned gamepads are dropped, since the only thing they can
+   * do is activate, which is handled by activate_pending().
+   */
   fn parse_input_buffer(&mut self) -> () {
     let mut new_buffer: Vec<(InputEvent, u8)> = vec!();
     while let Some((event, delay)) = self.input_buffer.pop() {
       if delay == 0 {
         if let Some(i) = self.input_map.get(event.get_gamepad_id()) {
-          self.model.update_pad(&i, &event);
-        } else {
-          if let InputEvent::GamepadButton(gamepad_id, button, value) = event {
-            if button == InputButton::RightBumper && value == 1.0 {
-              match self.connect(&gamepad_id) {
-                Ok(msg) => self.view.writeln(format!("{}", msg)),
-                Err(e) => self.view.writeln(format!("{}", e))
-              }
-            }
-          }
+          self.model.update_pad(i, &event);
         }
       } else {
         new_buffer.insert(0, (event, delay - 1));
@@ -366,34 +1470,133 @@ impl ClientController {
     self.input_buffer = new_buffer;
   }
 
+  /**
+   * Connects every unassigned gamepad that has held an activation chord for
+   * long enough. If a gamepad satisfies more than one activation, the one with
+   * the most buttons wins, so a chord like LB + RB can coexist with just RB.
+   *
+   * Each time a chord is held, connecting is only tried once. The chord itself
+   * is never sent to the Switch, since the gamepad had no slot when it was
+   * pressed.
+   */
+  fn activate_pending(&mut self) -> () {
+    for (gamepad_id, pressed) in self.pressed.clone() {
+      if self.input_map.contains_key(&gamepad_id) {
+        self.activating.remove(&gamepad_id);
+        continue;
+      }
+      match self.find_activation(&pressed) {
+        Some(j) => {
+          let (since, tried) = match self.activating.get(&gamepad_id) {
+            Some((k, since, tried)) if *k == j => (*since, *tried),
+            _ => (time::Instant::now(), false)
+          };
+          let activation: Activation = self.activations[j].clone();
+          let due: bool =
+            since.elapsed().as_millis() >= *activation.get_hold_ms() as u128;
+          self.activating.insert(gamepad_id, (j, since, tried || due));
+          if due && !tried {
+            let result: Result<String, String> =
+              self.connect(&gamepad_id, activation.get_switch_pad());
+            self.report(result);
+          }
+        },
+        None => {
+          self.activating.remove(&gamepad_id);
+        }
+      }
+    }
+  }
+
+  /**
+   * Returns the index of the most specific activation satisfied by a set of
+   * pressed buttons, if any.
+   */
+  fn find_activation(&self, pressed: &HashSet<InputButton>) -> Option<usize> {
+    let mut found: Option<usize> = None;
+    for (j, activation) in self.activations.iter().enumerate() {
+      if activation.is_satisfied_by(pressed) {
+        if let Some(k) = found {
+          if self.activations[k].get_buttons().len()
+            >= activation.get_buttons().len() {
+            continue;
+          }
+        }
+        found = Some(j);
+      }
+    }
+    return found;
+  }
+
   /**
    * Attempts to assign the given gamepad ID and switch pad type to an open
    * slot, while mapping said ID the corresponding index. Slots are open so as
    * long as they are not equal to None, or if the associated controller is
    * reported by the respective input reader as disconnected.
    *
+   * A slot held for a gamepad with this same name is always reclaimed first.
+   * Otherwise, held slots are treated as open and can be claimed by any
+   * gamepad.
+   *
+   * If a switch pad type is given, it's used instead of the slot's own type.
+   * Slots whose own type is Disconnected are never assigned to, though, and
+   * neither are locked slots.
+   *
    * Is O(n^2) in the context of parse_buffer(), but at least controller
    * assignment shouldn't happen often.
    */
-  fn connect(&mut self, gamepad_id: &usize) -> Result<String, String> {
-    let mut mapped: HashSet<&usize> = HashSet::new();
+  fn connect(
+    &mut self, gamepad_id: &usize, switch_pad: &Option<SwitchPad>
+  ) -> Result<String, String> {
+    let name: Option<String> = self.device_name(gamepad_id);
+    for (i, (_, held_name, _)) in self.held.clone() {
+      if held_name.is_some() && held_name == name && !self.locked.contains(&i) {
+        self.held.remove(&i);
+        self.input_map.insert(*gamepad_id, i);
+        if let Some(switch_pad) = switch_pad {
+          self.model.connect_pad(&i, switch_pad);
+        }
+        return Ok(
+          format!(
+            "Gamepad (id: {}) reclaimed held slot {}.",
+            &gamepad_id,
+            i + 1
+          )
+        );
+      }
+    }
+
+    // A slot reserved for this gamepad is tried before any other.
+    let mut order: Vec<usize> = c![
+      i, for i in 0..self.model.num_pads(), if self.is_reserved_for(&i, &name)
+    ];
+    order.extend(
+      c![
+        i,
+        for i in 0..self.model.num_pads(),
+        if self.slots[i].get_reserved_device().is_none()
+      ]
+    );
+    let mut mapped: HashSet<usize> = HashSet::new();
     for value in self.input_map.values() {
-      mapped.insert(value);
+      mapped.insert(*value);
     }
-    for i in 0..self.model.num_pads() {
-      if !mapped.contains(&i) {
-        let switch_pad: SwitchPad = self.switch_pads[i];
-        if switch_pad != SwitchPad::Disconnected {
-          self.input_map.insert(*gamepad_id, i);
-          self.model.connect_pad(&i, &switch_pad);
-          return Ok(
-            format!(
-              "Gamepad (id: {}) connected to slot {}.",
-              &gamepad_id,
-              i + 1
-            )
-          );
-        }
+    for i in order {
+      if !mapped.contains(&i) && !self.locked.contains(&i)
+        && *self.slots[i].get_switch_pad() != SwitchPad::Disconnected {
+        let switch_pad: SwitchPad =
+          switch_pad.unwrap_or(*self.slots[i].get_switch_pad());
+        self.held.remove(&i);
+        self.input_map.insert(*gamepad_id, i);
+        self.model.connect_pad(&i, &switch_pad);
+        return Ok(
+          format!(
+            "Gamepad (id: {}) connected to slot {} as a {:?}.",
+            &gamepad_id,
+            i + 1,
+            switch_pad
+          )
+        );
       }
     }
     return Err(
@@ -404,161 +1607,1164 @@ impl ClientController {
     );
   }
 
-  // Parses all buffered commands within the view.
+  /**
+   * Auto-assigns every gamepad that was connected since the last update, in the
+   * order they were connected. The first update after starting sees every
+   * gamepad as newly connected.
+   *
+   * A gamepad that couldn't be assigned is tried again on every update after,
+   * until it is. Only the first try is reported if it fails, though.
+   */
+  fn assign_new_devices(&mut self) -> () {
+    let mut connected: HashSet<usize> = HashSet::new();
+    for (gamepad_id, name) in self.input_adapter.get_devices() {
+      connected.insert(gamepad_id);
+      if self.input_map.contains_key(&gamepad_id) {
+        continue;
+      }
+      if !self.known_devices.contains(&gamepad_id) {
+        let result: Result<String, String> =
+          self.connect_preferred(&gamepad_id, &name);
+        if result.is_err() {
+          self.waiting_devices.insert(gamepad_id);
+        }
+        self.report(result);
+      } else if self.waiting_devices.contains(&gamepad_id) {
+        if let Ok(msg) = self.connect_preferred(&gamepad_id, &name) {
+          self.report(Ok(msg));
+        }
+      }
+    }
+    let input_map: &HashMap<usize, usize> = &self.input_map;
+    self.waiting_devices.retain(
+      |gamepad_id| connected.contains(gamepad_id)
+        && !input_map.contains_key(gamepad_id)
+    );
+    self.known_devices = connected;
+  }
+
+  /**
+   * Connects a gamepad to the slot preferred for its name if that slot is free,
+   * unlocked, and not set to Disconnected. Otherwise, this falls back to
+   * connecting it like usual.
+   */
+  fn connect_preferred(
+    &mut self, gamepad_id: &usize, name: &String
+  ) -> Result<String, String> {
+    if let Some(i) = self.preferred_slots.get(name).cloned() {
+      if i < self.model.num_pads()
+        && self.gamepad_at(&i).is_none()
+        && !self.locked.contains(&i)
+        && !self.is_reserved_for_other(&i, &Some(name.to_string()))
+        && *self.slots[i].get_switch_pad() != SwitchPad::Disconnected {
+        self.held.remove(&i);
+        self.input_map.insert(*gamepad_id, i);
+        self.model.connect_pad(&i, self.slots[i].get_switch_pad());
+        return Ok(
+          format!(
+            "Gamepad (id: {}) connected to its preferred slot {}.",
+            gamepad_id,
+            i + 1
+          )
+        );
+      }
+    }
+    return self.connect(gamepad_id, &None);
+  }
+
+  // Returns the name of a connected gamepad.
+  fn device_name(&mut self, gamepad_id: &usize) -> Option<String> {
+    for (id, name) in self.input_adapter.get_devices() {
+      if id == *gamepad_id {
+        return Some(name);
+      }
+    }
+    return None;
+  }
+
+  // Returns whether a slot is reserved for a gamepad with the given name.
+  fn is_reserved_for(&self, i: &usize, name: &Option<String>) -> bool {
+    return self.slots[*i].get_reserved_device().is_some()
+      && self.slots[*i].get_reserved_device() == name;
+  }
+
+  // Returns whether a slot is reserved for a gamepad without the given name.
+  fn is_reserved_for_other(&self, i: &usize, name: &Option<String>) -> bool {
+    return self.slots[*i].get_reserved_device().is_some()
+      && self.slots[*i].get_reserved_device() != name;
+  }
+
+  // Returns the ID of the gamepad assigned to a slot, if any.
+  fn gamepad_at(&self, i: &usize) -> Option<usize> {
+    for (gamepad_id, j) in &self.input_map {
+      if j == i {
+        return Some(*gamepad_id);
+      }
+    }
+    return None;
+  }
+
+  // Checks that a slot index actually refers to a slot.
+  fn check_slot(&self, i: &usize) -> Result<(), String> {
+    if *i < self.model.num_pads() {
+      return Ok(());
+    } else {
+      return Err(
+        format!(
+          "There is no slot {}. 'i' must be less than {}.",
+          i + 1,
+          self.model.num_pads()
+        )
+      );
+    }
+  }
+
+  // Checks that the client is running, since slots only exist while it is.
+  fn check_running(&self) -> Result<(), String> {
+    if self.running {
+      return Ok(());
+    } else {
+      return Err(
+        "The client needs to be running in order to manage slots.".to_string()
+      );
+    }
+  }
+
+  // Lists every gamepad the input adapter can see, along with its slot.
+  fn devices(&mut self) -> Result<String, String> {
+    let devices: Vec<(usize, String)> = self.input_adapter.get_devices();
+    if devices.is_empty() {
+      return Ok("No gamepads are connected.".to_string());
+    }
+    let mut lines: Vec<String> = vec!();
+    for (gamepad_id, name) in devices {
+      match self.input_map.get(&gamepad_id) {
+        Some(i) => lines.push(
+          format!("{}: {} (slot {})", gamepad_id, name, i + 1)
+        ),
+        None => lines.push(format!("{}: {} (unassigned)", gamepad_id, name))
+      }
+    }
+    return Ok(format!("Connected gamepads:\n{}", lines.join("\n")));
+  }
+
+  /**
+   * Assigns a gamepad to a slot by hand, moving it out of its current slot if
+   * it already has one. Unlike activating, this ignores whether the slot is
+   * locked, but the slot still has to be free.
+   */
+  fn assign(&mut self, gamepad_id: &usize, i: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_running().and(self.check_slot(i)) {
+      return Err(e);
+    }
+    if !self.input_adapter.is_connected(gamepad_id) {
+      return Err(
+        format!("No gamepad with an id of {} is connected.", gamepad_id)
+      );
+    }
+    if *self.slots[*i].get_switch_pad() == SwitchPad::Disconnected {
+      return Err(
+        format!(
+          "Slot {} is set to Disconnected, so it can't be assigned to.",
+          i + 1
+        )
+      );
+    }
+    if let Some(occupant) = self.gamepad_at(i) {
+      if occupant == *gamepad_id {
+        return Err(
+          format!(
+            "Gamepad (id: {}) is already assigned to slot {}.",
+            gamepad_id,
+            i + 1
+          )
+        );
+      } else {
+        return Err(
+          format!(
+            "Slot {} is taken by gamepad (id: {}). Kick it first with 'kick \
+            {}'.",
+            i + 1,
+            occupant,
+            i
+          )
+        );
+      }
+    }
+
+    if let Some(j) = self.input_map.remove(gamepad_id) {
+      self.model.disconnect_pad(&j);
+    }
+    self.held.remove(i);
+    self.activating.remove(gamepad_id);
+    self.input_map.insert(*gamepad_id, *i);
+    self.model.connect_pad(i, self.slots[*i].get_switch_pad());
+    self.model.neutralize_pad(i);
+    return Ok(
+      format!("Assigned gamepad (id: {}) to slot {}.", gamepad_id, i + 1)
+    );
+  }
+
+  // Disconnects whatever gamepad is in a slot, or releases the slot if held.
+  fn kick(&mut self, i: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_running().and(self.check_slot(i)) {
+      return Err(e);
+    }
+    if let Some(gamepad_id) = self.gamepad_at(i) {
+      return self.disconnect(&gamepad_id);
+    } else if self.release_held(i) {
+      return Ok(format!("Released held slot {}.", i + 1));
+    } else {
+      return Err(format!("Slot {} is empty.", i + 1));
+    }
+  }
+
+  /**
+   * Swaps the gamepads in two slots. Gamepads keep their Switch pad types when
+   * they move, so either slot can be empty as long as the other isn't.
+   */
+  fn swap(&mut self, a: &usize, b: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_running()
+      .and(self.check_slot(a))
+      .and(self.check_slot(b)) {
+      return Err(e);
+    }
+    if a == b {
+      return Err("A slot can't be swapped with itself.".to_string());
+    }
+    let gamepad_a: Option<usize> = self.gamepad_at(a);
+    let gamepad_b: Option<usize> = self.gamepad_at(b);
+    if gamepad_a.is_none() && gamepad_b.is_none() {
+      return Err(format!("Slots {} and {} are both empty.", a + 1, b + 1));
+    }
+
+    let switch_pad_a: SwitchPad = *self.model.get_pad(a).get_switch_pad();
+    let switch_pad_b: SwitchPad = *self.model.get_pad(b).get_switch_pad();
+    for (i, gamepad_id) in [(a, gamepad_a), (b, gamepad_b)] {
+      if let Some(gamepad_id) = gamepad_id {
+        self.input_map.remove(&gamepad_id);
+      }
+      self.held.remove(i);
+      self.model.disconnect_pad(i);
+    }
+    if let Some(gamepad_id) = gamepad_a {
+      self.input_map.insert(gamepad_id, *b);
+      self.model.connect_pad(b, &switch_pad_a);
+    }
+    if let Some(gamepad_id) = gamepad_b {
+      self.input_map.insert(gamepad_id, *a);
+      self.model.connect_pad(a, &switch_pad_b);
+    }
+    return Ok(format!("Swapped slots {} and {}.", a + 1, b + 1));
+  }
+
+  // Locks a slot so gamepads can't be assigned to it automatically.
+  fn lock(&mut self, i: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_slot(i) {
+      return Err(e);
+    }
+    if self.locked.insert(*i) {
+      return Ok(format!("Locked slot {}.", i + 1));
+    } else {
+      return Err(format!("Slot {} is already locked.", i + 1));
+    }
+  }
+
+  // Unlocks a slot so gamepads can be assigned to it automatically again.
+  fn unlock(&mut self, i: &usize) -> Result<String, String> {
+    if let Err(e) = self.check_slot(i) {
+      return Err(e);
+    }
+    if self.locked.remove(i) {
+      return Ok(format!("Unlocked slot {}.", i + 1));
+    } else {
+      return Err(format!("Slot {} isn't locked.", i + 1));
+    }
+  }
+
+  /**
+   * Parses all buffered commands within the view. Blank commands are skipped,
+   * but any command at all stops the status from being watched.
+   */
   fn parse_command_buffer(&mut self) -> () {
     while let Some(command) = self.view.get_command_buffer().pop() {
-      let parts: Vec<&str> = command.split(" ").collect::<Vec<&str>>();
-      match self.parse_command(&parts[0], &parts[1..]) {
-        Ok(msg) => self.view.writeln(format!("{}", msg)),
-        Err(e) => self.view.writeln(format!("{}", e))
-      }
+      self.execute(&command);
     }
   }
 
   /**
-   * Either returns a list of all available commands, or provides specific usage
-   * info about a given command.
+   * Runs a command as if it were entered into the view, and writes its result
+   * to the view.
    */
-  fn help(&self, command: Option<&str>) -> Result<String, String> {
-    return match command {
-      None => Ok(
-        "\n
-        help (command): Provides a list of available commands. You can specify \
-        a command after 'help' to view its full usage info.
-        \n
-        restart: Restarts the client. The client must be running.
-        \n
-        start: Starts the client.
-        \n
-        stop: Stops the client and disconnects all connected gamepads.
-        \n
-        exit: Same as 'stop', but totally exits the application.
-        \n
-        set_server_ip 'server_ip': \
-        Sets the server IP to whatever 'server_ip' is. Use 'help set_server_ip \
-        ' for full usage info.
-        \n
-        set_switch_pad 'i' 'switch_pad': \
-        Sets the Switch controller type of the gamepad at slot ('i' + 1). Use \
-        'help set_switch_pad' for full usage info.
-        \n
-        set_input_delay 'i' 'input_delay': \
-        Sets the input delay of the gamepad at slot ('i' + 1). Use 'help \
-        set_input_delay' for full usage info."
-        .to_string()
-      ),
-      Some(keyword) => {
-        match keyword {
-          "help" => Ok(
-            "\n
-            Usage: help (command)
-            \n
-            (command) can be the name of any command.
-            \n
-            Example, if you want to see the usage of 'set_server_ip':
-            \n
-            help set_server_ip"
-            .to_string()
-          ),
-          "restart" => Ok("\nUsage: restart".to_string()),
-          "start" => Ok("\nUsage: start".to_string()),
-          "stop" => Ok("\nUsage: stop".to_string()),
-          "exit" => Ok("\nUsage: exit".to_string()),
-          "set_server_ip" => Ok(
-            "\n
-            Usage: set_server_ip 'server_ip'
-            \n
-            Example, if your Switch's IP is 192.168.1.199:
-            \n
-            set_server_ip 192.168.1.199"
-            .to_string()
-          ),
-          "set_switch_pad" => Ok(
-            "\n
-            Usage: set_switch_pad 'i' 'switch_pad'
-            \n
-            'i' must be either 0 or a positive integer. It also represents the \
-            target index: slot numbers are always equal to 'i' + 1.
-            \n
-            switch_pad must be one of: Disconnected, ProController, \
-            JoyConLSide, or JoyConRSide.
-            \n
-            Example, if you want to set the controller in slot 2 to a sideways \
-            left JoyCon:
-            \n
-            set_switch_pad 1 JoyConLSide"
-            .to_string()
-          ),
-          "set_input_delay" => Ok(
-            "\n
-            Usage: set_input_delay 'i' 'input_delay'
-            \n
-            'i' must be either 0 or a positive integer. It also represents the \
-            target index: slot numbers are always equal to 'i' + 1.
-            \n
-            'input_delay' must be either 0 or a positive integer less than 256.
-            \n
-            Example, if you want to set the input delay of the controller in \
-            slot 3 to 6 frames:
-            \n
-            set_input_delay 2 6"
-            .to_string()
-          ),
-          _ => Err(format!("'{}' is not a valid command.", keyword))
+  pub fn execute(&mut self, command: &str) -> () {
+    if self.watch_ticks.is_some() {
+      self.watch_ticks = None;
+      self.view.writeln("Stopped watching status.".to_string());
+    }
+    let result: Result<String, String> = match command::parse(command) {
+      Ok(Some(command)) => self.run(command),
+      Ok(None) => return,
+      Err(e) => Err(e.to_string())
+    };
+    self.report(result);
+  }
+
+  /**
+   * Returns the path of the startup script, if there is one. Relative paths
+   * are taken to be relative to the folder the config is in.
+   */
+  fn startup_script_path(&self) -> Option<PathBuf> {
+    return self.startup_script.as_ref().map(|path| {
+      match self.config_path.parent() {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf()
+      }
+    });
+  }
+
+  /**
+   * Runs every command in a script, one per line, as if each was entered into
+   * the view. Blank lines and lines starting with '#' are skipped. The script
+   * stops at the first command that fails, and the error says which line it
+   * was on.
+   */
+  fn source(&mut self, path: &Path) -> Result<String, String> {
+    let script: String = match fs::read_to_string(path) {
+      Ok(script) => script,
+      Err(e) => return Err(
+        format!("Error occurred while reading {}: {}", path.display(), e)
+      )
+    };
+    let id: PathBuf = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    if self.sourcing.contains(&id) {
+      return Err(
+        format!(
+          "{} is already running, so it can't run itself.", path.display()
+        )
+      );
+    }
+    self.sourcing.push(id);
+    let mut ran: usize = 0;
+    let mut result: Result<(), String> = Ok(());
+    let mut cut_short: bool = false;
+    for (n, line) in script.lines().enumerate() {
+      if line.trim().starts_with('#') {
+        continue;
+      }
+      if self.is_exiting() {
+        cut_short = true;
+        break;
+      }
+      let was_running: bool = self.running;
+      let command_result: Result<String, String> = match command::parse(line) {
+        Ok(Some(command)) => self.run(command),
+        Ok(None) => continue,
+        Err(e) => Err(e.to_string())
+      };
+      match command_result {
+        Ok(msg) => {
+          self.view.writeln(msg);
+          ran += 1;
+        },
+        Err(e) => {
+          result = Err(
+            format!("{}, line {}: {}", path.display(), n + 1, e)
+          );
+          break;
         }
       }
+      // Stopping ends the script too, but restarting doesn't.
+      if was_running && !self.running && self.queued != Some(Command::Start) {
+        cut_short = true;
+        break;
+      }
+    }
+    self.sourcing.pop();
+    return match result {
+      Ok(_) if cut_short => Ok(
+        format!(
+          "Ran {} command(s) from {}, then skipped the rest since the client \
+          is stopping.",
+          ran,
+          path.display()
+        )
+      ),
+      Ok(_) => Ok(
+        format!("Ran {} command(s) from {}.", ran, path.display())
+      ),
+      Err(e) => Err(e)
     }
   }
 
+  // Returns whether the client is exiting, or about to once it's cleaned up.
+  fn is_exiting(&self) -> bool {
+    return self.exit_code.is_some()
+      || self.queued == Some(Command::Exit)
+      || self.exit_flag.load(Ordering::Relaxed);
+  }
+
   /**
-   * Parses a given command. A command is decided by a keyword and its
-   * associated arguments.
+   * Saves the overridden settings to the config, as if they were each changed
+   * with a command.
    */
-  fn parse_command(
-    &mut self, keyword: &str, args: &[&str]
-  ) -> Result<String, String> {
-    return match keyword {
-      "help" => {
-        if args.len() >= 1 {
-          return self.help(Some(args[0]));
-        } else {
-          return self.help(None);
-        }
-      },
-      "restart" => self.restart(),
-      "start" => self.start(),
-      "stop" => self.stop(),
-      "exit" => self.exit(),
-      "set_server_ip" => {
-        if args.len() >= 1 {
-          return self.set_server_ip(&args[0].to_string());
-        } else {
-          return Err(self.help(Some("set_server_ip")).unwrap());
-        }
-      },
-      "set_switch_pad" => {
-        if args.len() >= 2 {
-          if let Ok(i) = args[0].parse::<usize>() {
-            if let Ok(switch_pad) = SwitchPad::from_str(args[1]) {
-              return self.set_switch_pad(&i, &switch_pad);
-            }
-          }
-        }
-        return Err(self.help(Some("set_switch_pad")).unwrap());
-      },
-      "set_input_delay" => {
-        if args.len() >= 2 {
-          if let Ok(i) = args[0].parse::<usize>() {
-            if let Ok(input_delay) = args[1].parse::<u8>() {
-              return self.set_input_delay(&i, &input_delay);
-            }
-          }
-        }
-        return Err(self.help(Some("set_input_delay")).unwrap());
+  pub fn save_overrides(&mut self) -> () {
+    self.overrides.clear();
+    let result: Result<String, String> = self.save_config();
+    self.report(result);
+  }
+
+  // Runs a command that was already parsed.
+  fn run(&mut self, command: Command) -> Result<String, String> {
+    return match command {
+      Command::Help(name) => command::help(name.as_deref()),
+      Command::Restart => self.restart(),
+      Command::Start => self.start(),
+      Command::Stop => self.stop(),
+      Command::Exit => self.exit(),
+      Command::ReleaseAll => self.release_all(),
+      Command::Status(watch) => self.show_status(watch),
+      Command::Stats => Ok(format!("{}", self.stats_snapshot())),
+      Command::Devices => self.devices(),
+      Command::Assign(gamepad_id, i) => self.assign(&gamepad_id, &i),
+      Command::Kick(i) => self.kick(&i),
+      Command::Swap(a, b) => self.swap(&a, &b),
+      Command::Lock(i) => self.lock(&i),
+      Command::Unlock(i) => self.unlock(&i),
+      Command::ShowSlot(i) => self.show_slot(&i),
+      Command::SetSlot(i, key, value) => self.set_slot(&i, &key, &value),
+      Command::Profile(action) => match action {
+        ProfileAction::Save(name) => self.save_profile(&name),
+        ProfileAction::Load(name) => self.load_profile(&name),
+        ProfileAction::List => self.list_profiles(),
+        ProfileAction::Delete(name) => self.delete_profile(&name)
       },
-      _ => Err(format!("'{}' is not a valid command.", keyword))
+      Command::Reload => self.reload_config(),
+      Command::Source(path) => self.source(Path::new(&path)),
+      Command::SetServerIp(server_ip) => self.set_server_ip(&server_ip),
+      Command::SetAutoAssign(auto_assign) =>
+        self.set_auto_assign(&auto_assign),
+      Command::SetPreferredSlot(gamepad_id, i) =>
+        self.set_preferred_slot(&gamepad_id, &i)
     }
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{
+    cell::RefCell,
+    env,
+    net::UdpSocket,
+    process,
+    rc::Rc,
+    thread
+  };
+
+  /**
+   * Represents everything a fake view was told, along with the commands it's
+   * about to hand over. This is shared with the test driving it.
+   */
+  #[derive(Default)]
+  struct Screen {
+    lines: Vec<String>,
+    commands: Vec<String>,
+    watched: usize,
+    closed: bool
+  }
+
+  struct FakeView {
+    screen: Rc<RefCell<Screen>>
+  }
+
+  impl ClientView for FakeView {
+    fn update(&mut self) -> Result<(), String> {
+      return Ok(());
+    }
+
+    fn write(&mut self, s: String) -> () {
+      self.screen.borrow_mut().lines.push(s);
+    }
+
+    fn writeln(&mut self, s: String) -> () {
+      self.screen.borrow_mut().lines.push(s);
+    }
+
+    fn watch_status(&mut self, _status: &ClientStatus) -> () {
+      self.screen.borrow_mut().watched += 1;
+    }
+
+    fn update_status(&mut self, _status: &ClientStatus) -> () {}
+
+    fn close(&mut self) -> () {
+      self.screen.borrow_mut().closed = true;
+    }
+
+    fn get_command_buffer(&mut self) -> Vec<String> {
+      return self.screen.borrow_mut().commands.drain(..).collect();
+    }
+  }
+
+  /**
+   * Represents the gamepads plugged into a fake input adapter, along with the
+   * events they're about to send. This is shared with the test driving it.
+   */
+  #[derive(Default)]
+  struct Gamepads {
+    devices: Vec<(usize, String)>,
+    events: Vec<InputEvent>
+  }
+
+  struct FakeAdapter {
+    gamepads: Rc<RefCell<Gamepads>>,
+    dropped: u64
+  }
+
+  impl InputAdapter for FakeAdapter {
+    fn read(&mut self) -> Vec<InputEvent> {
+      return self.gamepads.borrow_mut().events.drain(..).collect();
+    }
+
+    fn is_connected(&mut self, gamepad_id: &usize) -> bool {
+      return self.gamepads.borrow()
+        .devices
+        .iter()
+        .any(|(id, _)| id == gamepad_id);
+    }
+
+    fn get_devices(&mut self) -> Vec<(usize, String)> {
+      return self.gamepads.borrow().devices.clone();
+    }
+
+    fn get_name(&self) -> &str {
+      return "fake";
+    }
+
+    fn get_dropped(&self) -> &u64 {
+      return &self.dropped;
+    }
+  }
+
+  /**
+   * Represents a controller hooked up to a fake view and input adapter, whose
+   * model sends to a stand-in for the input server on localhost.
+   */
+  struct Harness {
+    controller: ClientController,
+    screen: Rc<RefCell<Screen>>,
+    gamepads: Rc<RefCell<Gamepads>>,
+    server: UdpSocket,
+    dir: PathBuf
+  }
+
+  impl Harness {
+    /**
+     * Constructs and initializes a controller, with the given TOML added to a
+     * config that points at the stand-in server. Each test needs its own name,
+     * since that's where its config is kept.
+     */
+    fn new(name: &str, toml: &str) -> Harness {
+      return Harness::with_overrides(name, toml, ConfigOverrides::default());
+    }
+
+    // Like new(), but with settings overridden as if from the command line.
+    fn with_overrides(
+      name: &str, toml: &str, overrides: ConfigOverrides
+    ) -> Harness {
+      let dir: PathBuf = env::temp_dir()
+        .join(format!("client-rs-controller-{}-{}", process::id(), name));
+      let _ = fs::remove_dir_all(&dir);
+      fs::create_dir_all(&dir).unwrap();
+      let server: UdpSocket = UdpSocket::bind("127.0.0.1:0").unwrap();
+      server.set_nonblocking(true).unwrap();
+      write_config(&dir, &server, toml);
+      let screen: Rc<RefCell<Screen>> =
+        Rc::new(RefCell::new(Screen::default()));
+      let gamepads: Rc<RefCell<Gamepads>> =
+        Rc::new(RefCell::new(Gamepads::default()));
+      let mut controller: ClientController = ClientController::new(
+        ClientModel::bind("127.0.0.1:0").unwrap(),
+        Box::new(FakeView { screen: screen.clone() }),
+        Box::new(FakeAdapter { gamepads: gamepads.clone(), dropped: 0 }),
+        dir.join("config.toml"),
+        overrides
+      );
+      controller.initialize().unwrap();
+      return Harness {
+        controller: controller,
+        screen: screen,
+        gamepads: gamepads,
+        server: server,
+        dir: dir
+      }
+    }
+
+    // Rewrites the config with different TOML, then reloads it.
+    fn reload(&mut self, toml: &str) -> String {
+      write_config(&self.dir, &self.server, toml);
+      return self.run("reload");
+    }
+
+    // Updates the controller, returning the status it exited with, if any.
+    fn tick(&mut self) -> Option<i32> {
+      return self.controller.update();
+    }
+
+    // Runs a command, returning what it wrote to the view.
+    fn run(&mut self, command: &str) -> String {
+      let before: usize = self.screen.borrow().lines.len();
+      self.controller.execute(command);
+      return self.screen.borrow().lines[before..].join("\n");
+    }
+
+    // Returns how many lines written to the view so far contain some text.
+    fn count(&self, text: &str) -> usize {
+      return self.screen.borrow()
+        .lines
+        .iter()
+        .filter(|line| line.contains(text))
+        .count();
+    }
+
+    fn plug(&mut self, gamepad_id: usize, name: &str) -> () {
+      self.gamepads.borrow_mut().devices.push((gamepad_id, name.to_string()));
+    }
+
+    fn unplug(&mut self, gamepad_id: usize) -> () {
+      self.gamepads.borrow_mut().devices.retain(|(id, _)| *id != gamepad_id);
+    }
+
+    fn press(&mut self, gamepad_id: usize, button: InputButton) -> () {
+      self.gamepads.borrow_mut()
+        .events
+        .push(InputEvent::GamepadButton(gamepad_id, button, 1.0));
+    }
+
+    fn release(&mut self, gamepad_id: usize, button: InputButton) -> () {
+      self.gamepads.borrow_mut()
+        .events
+        .push(InputEvent::GamepadButton(gamepad_id, button, 0.0));
+    }
+
+    // Returns how many packets the stand-in server has received so far.
+    fn packets(&self) -> usize {
+      let mut buf: [u8; 256] = [0; 256];
+      let mut count: usize = 0;
+      while self.server.recv(&mut buf).is_ok() {
+        count += 1;
+      }
+      return count;
+    }
+  }
+
+  // Writes a config that points at the stand-in server, with some TOML added.
+  fn write_config(dir: &Path, server: &UdpSocket, toml: &str) -> () {
+    fs::write(
+      dir.join("config.toml"),
+      format!(
+        "version = {}\nserver_ip = \"127.0.0.1\"\nserver_port = {}\n{}",
+        config::CONFIG_VERSION,
+        server.local_addr().unwrap().port(),
+        toml
+      )
+    ).unwrap();
+  }
+
+  // Returns TOML for four slots, with some settings for the first one.
+  fn slots(first: &str) -> String {
+    return format!("[[slot]]\n{}\n{}", first, "[[slot]]\n".repeat(3));
+  }
+
+  impl Drop for Harness {
+    fn drop(&mut self) -> () {
+      let _ = fs::remove_dir_all(&self.dir);
+    }
+  }
+
+  #[test]
+  fn activation_connects_gamepad() {
+    let mut h: Harness = Harness::new("activation_connects", "");
+    h.run("start");
+    h.plug(3, "Pad");
+    h.press(3, InputButton::RightBumper);
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), Some(3));
+    assert_eq!(h.count("Gamepad (id: 3) connected to slot 1"), 1);
+    assert!(h.packets() > 0);
+  }
+
+  #[test]
+  fn failed_activation_tried_once_per_press() {
+    let mut h: Harness = Harness::new("activation_once", "");
+    for i in 0..4 {
+      h.run(&format!("lock {}", i));
+    }
+    h.run("start");
+    h.plug(3, "Pad");
+    h.press(3, InputButton::RightBumper);
+    for _i in 0..10 {
+      h.tick();
+    }
+    assert_eq!(h.count("Couldn't connect gamepad (id: 3)"), 1);
+
+    // Unlocking a slot doesn't connect it until the chord is held again.
+    h.run("unlock 0");
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), None);
+    h.release(3, InputButton::RightBumper);
+    h.tick();
+    h.press(3, InputButton::RightBumper);
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), Some(3));
+    assert_eq!(h.count("Couldn't connect gamepad (id: 3)"), 1);
+  }
+
+  #[test]
+  fn reload_reconnects_retyped_slot() {
+    let mut h: Harness = Harness::new("reload_retyped", &slots(""));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    let out: String = h.reload(&slots("switch_pad = \"JoyConLSide\""));
+    assert!(out.contains("Applied: slot 1 switch_pad."), "{}", out);
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(), SwitchPad::JoyConLSide
+    );
+    assert_eq!(h.controller.gamepad_at(&0), Some(3));
+    assert_eq!(
+      *h.controller.current_config().get_slots()[0].get_switch_pad(),
+      SwitchPad::JoyConLSide
+    );
+  }
+
+  #[test]
+  fn reload_empties_slot_set_to_disconnected() {
+    let mut h: Harness = Harness::new("reload_emptied", &slots(""));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    h.reload(&slots("switch_pad = \"Disconnected\""));
+    assert_eq!(h.controller.gamepad_at(&0), None);
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(), SwitchPad::Disconnected
+    );
+  }
+
+  #[test]
+  fn waiting_gamepad_assigned_once_slot_frees() {
+    let mut h: Harness =
+      Harness::new("auto_assign_waits", "auto_assign = true");
+    h.run("start");
+    for gamepad_id in 0..5 {
+      h.plug(gamepad_id, "Pad");
+    }
+    for _i in 0..10 {
+      h.tick();
+    }
+    assert_eq!(h.count("Couldn't connect gamepad (id: 4)"), 1);
+
+    // The kicked gamepad isn't assigned again, but the waiting one is.
+    h.run("kick 0");
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), Some(4));
+    assert!(!h.controller.input_map.contains_key(&0));
+  }
+
+  #[test]
+  fn script_ends_once_client_stops() {
+    let mut h: Harness = Harness::new("source_stop", "");
+    fs::write(h.dir.join("script.txt"), "start\nstop\nstart\n").unwrap();
+    let script: String = h.dir.join("script.txt").display().to_string();
+    let out: String = h.run(&format!("source '{}'", script));
+    assert!(out.contains("Ran 2 command(s)"), "{}", out);
+    assert!(!h.controller.running);
+    assert_eq!(h.controller.queued, None);
+  }
+
+  #[test]
+  fn script_ends_once_client_exits() {
+    let mut h: Harness = Harness::new("source_exit", "");
+    fs::write(h.dir.join("script.txt"), "exit\nstart\n").unwrap();
+    let script: String = h.dir.join("script.txt").display().to_string();
+    let out: String = h.run(&format!("source '{}'", script));
+    assert!(out.contains("Ran 1 command(s)"), "{}", out);
+    assert!(!h.controller.running);
+    assert_eq!(h.tick(), Some(0));
+  }
+
+  #[test]
+  fn release_all_waits_out_outage() {
+    let mut h: Harness = Harness::new("release_all_outage", "");
+    h.run("start");
+    let mut outage: Outage = Outage::new(time::Duration::from_secs(60));
+    outage.fail();
+    h.controller.outage = Some(outage);
+    h.run("release_all");
+    assert_eq!(h.packets(), 0);
+
+    h.controller.outage = None;
+    h.run("release_all");
+    assert_eq!(h.packets(), 1);
+    assert_eq!(*h.controller.status().get_stats().get_packets_sent(), 1);
+  }
+
+  #[test]
+  fn held_slot_reclaimed_by_same_device() {
+    let mut h: Harness =
+      Harness::new("hold_reclaim", &slots("hold_timeout = 30"));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.tick();
+    h.run("assign 3 0");
+    h.unplug(3);
+    h.tick();
+    assert!(h.controller.held.contains_key(&0));
+    assert_eq!(h.controller.gamepad_at(&0), None);
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::ProController
+    );
+
+    // The same device comes back under a new id.
+    h.plug(7, "Pad");
+    h.press(7, InputButton::RightBumper);
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), Some(7));
+    assert!(h.controller.held.is_empty());
+    assert_eq!(h.count("Gamepad (id: 7) reclaimed held slot 1."), 1);
+  }
+
+  #[test]
+  fn held_slot_released_after_timeout() {
+    let mut h: Harness =
+      Harness::new("hold_timeout", &slots("hold_timeout = 1"));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    h.unplug(3);
+    h.tick();
+    let since: time::Instant = time::Instant::now()
+      .checked_sub(time::Duration::from_secs(2))
+      .unwrap();
+    h.controller.held.get_mut(&0).unwrap().2 = since;
+    h.tick();
+    assert!(h.controller.held.is_empty());
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::Disconnected
+    );
+    assert_eq!(h.count("Slot 1 was not reclaimed in time"), 1);
+  }
+
+  #[test]
+  fn unheld_slot_disconnected_right_away() {
+    let mut h: Harness = Harness::new("hold_none", &slots(""));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    h.unplug(3);
+    h.tick();
+    assert!(h.controller.held.is_empty());
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::Disconnected
+    );
+  }
+
+  #[test]
+  fn assign_moves_gamepad_between_slots() {
+    let mut h: Harness = Harness::new("assign_moves", &slots(""));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.plug(4, "Pad");
+    h.run("assign 3 0");
+    h.run("assign 3 2");
+    assert_eq!(h.controller.gamepad_at(&0), None);
+    assert_eq!(h.controller.gamepad_at(&2), Some(3));
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::Disconnected
+    );
+
+    let out: String = h.run("assign 4 2");
+    assert!(out.starts_with("Slot 3 is taken by gamepad (id: 3)"), "{}", out);
+    assert_eq!(h.controller.gamepad_at(&2), Some(3));
+  }
+
+  #[test]
+  fn assign_refuses_disconnected_slot() {
+    let mut h: Harness = Harness::new(
+      "assign_disconnected", &slots("switch_pad = \"Disconnected\"")
+    );
+    h.run("start");
+    h.plug(3, "Pad");
+    let out: String = h.run("assign 3 0");
+    assert!(out.contains("set to Disconnected"), "{}", out);
+    assert_eq!(h.controller.gamepad_at(&0), None);
+  }
+
+  #[test]
+  fn kick_empties_slot() {
+    let mut h: Harness = Harness::new("kick_empties", &slots(""));
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 1");
+    h.run("kick 1");
+    assert_eq!(h.controller.gamepad_at(&1), None);
+    assert_eq!(
+      *h.controller.model.get_pad(&1).get_switch_pad(),
+      SwitchPad::Disconnected
+    );
+    assert!(h.run("kick 1").contains("empty"));
+  }
+
+  #[test]
+  fn swap_exchanges_gamepads_and_types() {
+    let mut h: Harness = Harness::new(
+      "swap_exchanges", &slots("switch_pad = \"JoyConLSide\"")
+    );
+    h.run("start");
+    h.plug(3, "Pad");
+    h.plug(4, "Pad");
+    h.run("assign 3 0");
+    h.run("assign 4 1");
+    h.run("swap 0 1");
+    assert_eq!(h.controller.gamepad_at(&0), Some(4));
+    assert_eq!(h.controller.gamepad_at(&1), Some(3));
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::ProController
+    );
+    assert_eq!(
+      *h.controller.model.get_pad(&1).get_switch_pad(),
+      SwitchPad::JoyConLSide
+    );
+  }
 
+  #[test]
+  fn locked_slot_skipped_by_activation_but_not_assign() {
+    let mut h: Harness = Harness::new("lock_skipped", &slots(""));
+    h.run("lock 0");
+    h.run("start");
+    h.plug(3, "Pad");
+    h.plug(4, "Pad");
+    h.press(3, InputButton::RightBumper);
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&1), Some(3));
+    h.run("assign 4 0");
+    assert_eq!(h.controller.gamepad_at(&0), Some(4));
+  }
+
+  #[test]
+  fn auto_assign_prefers_slot_for_name() {
+    let mut h: Harness = Harness::new(
+      "preferred_slot", "auto_assign = true\n[preferred_slots]\nPad = 2\n"
+    );
+    h.run("start");
+    h.plug(3, "Pad");
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&2), Some(3));
+    assert_eq!(h.count("connected to its preferred slot 3"), 1);
+
+    // Another gamepad with the same name falls back to the first free slot.
+    h.plug(4, "Pad");
+    h.tick();
+    assert_eq!(h.controller.gamepad_at(&0), Some(4));
+  }
+
+  #[test]
+  fn set_preferred_slot_saved_by_name() {
+    let mut h: Harness = Harness::new("preferred_saved", "");
+    h.plug(3, "Pad");
+    h.run("set_preferred_slot 3 1");
+    let saved: String = fs::read_to_string(h.dir.join("config.toml")).unwrap();
+    assert!(saved.contains("[preferred_slots]\nPad = 1"), "{}", saved);
+    h.run("set_preferred_slot 3 none");
+    assert!(h.controller.preferred_slots.is_empty());
+  }
+
+  #[test]
+  fn status_counts_packets_since_start() {
+    let mut h: Harness = Harness::new("status_packets", "cleanup_ms = 0");
+    h.run("start");
+    for _i in 0..3 {
+      h.tick();
+    }
+    assert_eq!(*h.controller.status().get_packets_sent(), 3);
+    assert_eq!(h.packets(), 3);
+
+    h.run("stop");
+    h.tick();
+    h.run("start");
+    assert_eq!(*h.controller.status().get_packets_sent(), 0);
+  }
+
+  #[test]
+  fn status_shows_gamepad_and_buttons() {
+    let mut h: Harness = Harness::new("status_slots", "");
+    h.run("start");
+    h.plug(3, "Pad");
+    h.tick();
+    h.run("assign 3 0");
+    h.press(3, InputButton::South);
+    h.tick();
+    let status: ClientStatus = h.controller.status();
+    let slot: &SlotStatus = &status.get_slots()[0];
+    assert_eq!(*slot.get_gamepad(), Some((3, "Pad".to_string())));
+    assert_eq!(slot.get_buttons(), vec!("B"));
+    assert_eq!(*status.get_slots()[1].get_gamepad(), None);
+    assert!(h.run("status").contains("Pad"));
+  }
+
+  #[test]
+  fn status_watch_redraws_until_command() {
+    let mut h: Harness = Harness::new("status_watch", "");
+    h.run("status --watch");
+    for _i in 0..((WATCH_INTERVAL + 1) * 2 + 1) {
+      h.tick();
+    }
+    assert_eq!(h.screen.borrow().watched, 3);
+    assert!(h.run("devices").contains("Stopped watching status."));
+    h.tick();
+    assert_eq!(h.screen.borrow().watched, 3);
+  }
+
+  #[test]
+  fn overrides_used_but_not_saved() {
+    let overrides: ConfigOverrides = ConfigOverrides::new(
+      None,
+      None,
+      vec!((0, SwitchPad::JoyConLSide)).into_iter().collect(),
+      vec!((1, 4)).into_iter().collect()
+    );
+    let mut h: Harness =
+      Harness::with_overrides("overrides_unsaved", &slots(""), overrides);
+    assert_eq!(h.count("overridden"), 1);
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::JoyConLSide
+    );
+
+    // Saving for another reason keeps what the config had.
+    h.run("set_auto_assign true");
+    let (saved, _) = config::load(&h.dir.join("config.toml"), 4).unwrap();
+    assert_eq!(
+      *saved.get_slots()[0].get_switch_pad(), SwitchPad::ProController
+    );
+    assert_eq!(*saved.get_slots()[1].get_input_delay(), 0);
+    assert!(*saved.get_auto_assign());
+
+    // Changing an overridden setting on purpose saves it.
+    h.run("slot 1 set input_delay 2");
+    let (saved, _) = config::load(&h.dir.join("config.toml"), 4).unwrap();
+    assert_eq!(*saved.get_slots()[1].get_input_delay(), 2);
+    assert_eq!(
+      *saved.get_slots()[0].get_switch_pad(), SwitchPad::ProController
+    );
+  }
+
+  #[test]
+  fn profile_round_trip() {
+    let mut h: Harness = Harness::new("profile_round_trip", &slots(""));
+    h.run("slot 0 set switch_pad JoyConLSide");
+    assert!(h.run("profile save smash").contains("smash"));
+    h.run("set_server_ip 10.0.0.9");
+    h.run("slot 0 set switch_pad ProController");
+    assert_eq!(h.run("profile list"), "Saved profiles:\nsmash");
+
+    h.run("profile load smash");
+    assert_eq!(h.controller.model.get_server_ip(), "127.0.0.1");
+    assert_eq!(
+      *h.controller.slots[0].get_switch_pad(), SwitchPad::JoyConLSide
+    );
+    let (saved, _) = config::load(&h.dir.join("config.toml"), 4).unwrap();
+    assert_eq!(saved.get_server_ip(), "127.0.0.1");
+
+    h.run("profile delete smash");
+    assert_eq!(h.run("profile list"), "No profiles have been saved.");
+    assert!(h.run("profile load smash").contains("no profile named"));
+  }
+
+  #[test]
+  fn profile_load_reconnects_retyped_slot() {
+    let mut h: Harness = Harness::new("profile_retyped", &slots(""));
+    h.run("slot 0 set switch_pad JoyConRSide");
+    h.run("profile save joycon");
+    h.run("slot 0 set switch_pad ProController");
+    h.run("start");
+    h.plug(3, "Pad");
+    h.run("assign 3 0");
+    let out: String = h.run("profile load joycon");
+    assert_eq!(out, "Profile 'joycon' successfully loaded.");
+    assert_eq!(
+      *h.controller.model.get_pad(&0).get_switch_pad(),
+      SwitchPad::JoyConRSide
+    );
+    assert_eq!(h.controller.gamepad_at(&0), Some(3));
+  }
+
+  #[test]
+  fn cleanup_sends_one_packet_per_tick_until_done() {
+    let mut h: Harness = Harness::new("cleanup_ticks", "cleanup_ms = 150");
+    h.run("start");
+    h.run("stop");
+    h.packets();
+    for _i in 0..5 {
+      assert_eq!(h.tick(), None);
+    }
+    assert_eq!(h.packets(), 5);
+    assert!(h.controller.model.is_cleaning_up());
+    assert!(h.controller.status().get_cleanup().is_some());
+    assert_eq!(h.count("Gamepads should now be cleaned up."), 0);
+
+    thread::sleep(time::Duration::from_millis(150));
+    h.tick();
+    assert!(!h.controller.model.is_cleaning_up());
+    assert_eq!(h.count("Gamepads should now be cleaned up."), 1);
+  }
+
+  #[test]
+  fn start_queued_until_cleanup_done() {
+    let mut h: Harness = Harness::new("cleanup_start", "cleanup_ms = 50");
+    h.run("start");
+    h.run("stop");
+    assert_eq!(
+      h.run("start"), "The client will start once it's done cleaning up."
+    );
+    h.tick();
+    assert!(!h.controller.running);
+    thread::sleep(time::Duration::from_millis(50));
+    h.tick();
+    assert!(h.controller.running);
+    assert_eq!(h.count("The client is ready to receive inputs."), 2);
+  }
+
+  #[test]
+  fn stop_cancels_queued_start() {
+    let mut h: Harness = Harness::new("cleanup_cancel", "cleanup_ms = 0");
+    h.run("start");
+    h.run("stop");
+    h.run("start");
+    assert!(h.run("stop").contains("won't start"));
+    h.tick();
+    assert!(!h.controller.running);
+    assert_eq!(h.controller.queued, None);
+  }
+
+  #[test]
+  fn exit_waits_for_cleanup_and_refuses_start() {
+    let mut h: Harness = Harness::new("cleanup_exit", "cleanup_ms = 50");
+    h.run("start");
+    assert!(h.run("exit").ends_with("The client will exit once that's done."));
+    assert_eq!(
+      h.run("start"), "The client is about to exit, so it can't be started."
+    );
+    assert_eq!(h.tick(), None);
+    thread::sleep(time::Duration::from_millis(50));
+    assert_eq!(h.tick(), Some(0));
+    assert!(h.screen.borrow().closed);
+  }
+}
@@ -1,5 +1,10 @@
+use serde::{Serialize, Deserialize};
+use strum_macros::EnumString;
+
 // Represents the buttons that are universally available on gamepads.
-#[derive(PartialEq, Debug)]
+#[derive(
+  Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize, EnumString
+)]
 pub enum InputButton {
   North,
   South,
@@ -53,4 +58,16 @@ pub trait InputAdapter {
 
   // Checks the input library to verify if a gamepad of a given ID is connected.
   fn is_connected(&mut self, gamepad_id: &usize) -> bool;
+
+  // Returns the IDs and names of all gamepads connected to the input library.
+  fn get_devices(&mut self) -> Vec<(usize, String)>;
+
+  // Returns the name of the input library, as it's shown to users.
+  fn get_name(&self) -> &str;
+
+  /**
+   * Returns how many events were dropped for having no mapping, since the
+   * adapter was constructed.
+   */
+  fn get_dropped(&self) -> &u64;
 }
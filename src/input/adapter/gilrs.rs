@@ -12,7 +12,6 @@ use gilrs::{
   Button,
   Axis
 };
-use std::convert::TryInto;
 
 /**
  * Represents a cross-platform input adapter that will read from a GilRs
@@ -24,14 +23,16 @@ use std::convert::TryInto;
  * theoretically work with Unix OS's.
  */
 pub struct GilrsAdapter {
-  gilrs: Gilrs
+  gilrs: Gilrs,
+  dropped: u64
 }
 
 impl GilrsAdapter {
   // Constructs a GilRs input adapter with an accompanying GilRs instance.
   pub fn new() -> GilrsAdapter {
     return GilrsAdapter {
-      gilrs: Gilrs::new().unwrap()
+      gilrs: Gilrs::new().unwrap(),
+      dropped: 0
     }
   }
 
@@ -42,7 +43,7 @@ impl GilrsAdapter {
     return match self.to_button(button) {
       Ok(mapped) => Ok(
         InputEvent::GamepadButton(
-          (*gamepad_id).try_into().unwrap(),
+          (*gamepad_id).into(),
           mapped,
           *value
         )
@@ -58,7 +59,7 @@ impl GilrsAdapter {
     return match self.to_axis(axis) {
       Ok(mapped) => Ok(
         InputEvent::GamepadAxis(
-          (*gamepad_id).try_into().unwrap(),
+          (*gamepad_id).into(),
           mapped,
           *value
         )
@@ -110,13 +111,13 @@ impl InputAdapter for GilrsAdapter {
         EventType::ButtonChanged(button, value, _) => {
           match self.to_button_event(&gamepad_id, &button, &value) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         EventType::AxisChanged(axis, value, _) => {
           match self.to_axis_event(&gamepad_id, &axis, &value) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         _ => ()
@@ -131,10 +132,27 @@ impl InputAdapter for GilrsAdapter {
    */
   fn is_connected(&mut self, gamepad_id: &usize) -> bool {
     for (id, _) in self.gilrs.gamepads() {
-      if *gamepad_id == id.try_into().unwrap() {
+      let id: usize = id.into();
+      if *gamepad_id == id {
         return true;
       }
     }
     return false;
   }
+
+  fn get_devices(&mut self) -> Vec<(usize, String)> {
+    let mut devices: Vec<(usize, String)> = vec!();
+    for (id, gamepad) in self.gilrs.gamepads() {
+      devices.push((id.into(), gamepad.name().to_string()));
+    }
+    return devices;
+  }
+
+  fn get_name(&self) -> &str {
+    return "GilRs";
+  }
+
+  fn get_dropped(&self) -> &u64 {
+    return &self.dropped;
+  }
 }
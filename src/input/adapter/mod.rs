@@ -1,4 +1,7 @@
 pub mod common;
+#[cfg(feature = "gilrs")]
 pub mod gilrs;
+#[cfg(feature = "multiinput")]
 pub mod multiinput;
+#[cfg(feature = "sdl")]
 pub mod sdl;
@@ -25,7 +25,8 @@ use multiinput::{
  * been tested. Do not expect an exquisite amount of support from this.
  */
 pub struct MultiInputAdapter {
-  manager: RawInputManager
+  manager: RawInputManager,
+  dropped: u64
 }
 
 impl MultiInputAdapter {
@@ -48,7 +49,8 @@ impl MultiInputAdapter {
       )
     );
     return MultiInputAdapter {
-      manager: manager
+      manager: manager,
+      dropped: 0
     }
   } 
 
@@ -60,13 +62,13 @@ impl MultiInputAdapter {
         RawEvent::JoystickButtonEvent(device_id, button, state) => {
           match self.to_button_event(&device_id, &button, &state) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         RawEvent::JoystickAxisEvent(device_id, axis, value) => {
           match self.to_axis_event(&device_id, &axis, &value) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         RawEvent::JoystickHatSwitchEvent(device_id, hat_switch) => {
@@ -198,6 +200,26 @@ impl InputAdapter for MultiInputAdapter {
   fn is_connected(&mut self, gamepad_id: &usize) -> bool {
     return self.manager.get_joystick_state(*gamepad_id).is_some();
   }
+
+  // Joystick IDs in multiinput are just their indices in the device list.
+  fn get_devices(&mut self) -> Vec<(usize, String)> {
+    let mut devices: Vec<(usize, String)> = vec!();
+    for (device_id, joystick) in self.manager.get_device_list()
+      .joysticks
+      .iter()
+      .enumerate() {
+      devices.push((device_id, joystick.name.to_string()));
+    }
+    return devices;
+  }
+
+  fn get_name(&self) -> &str {
+    return "multiinput";
+  }
+
+  fn get_dropped(&self) -> &u64 {
+    return &self.dropped;
+  }
 }
 
 /**
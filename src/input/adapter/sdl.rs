@@ -39,7 +39,8 @@ pub struct SdlAdapter {
   game_controller: GameControllerSubsystem, 
   event_pump: EventPump,
   _joystick: JoystickSubsystem,
-  _video: VideoSubsystem
+  _video: VideoSubsystem,
+  dropped: u64
 }
 
 impl SdlAdapter {
@@ -57,7 +58,8 @@ impl SdlAdapter {
       game_controller: game_controller, 
       event_pump: event_pump,
       _joystick: joystick,
-      _video: video
+      _video: video,
+      dropped: 0
     }
   }
 
@@ -115,7 +117,7 @@ impl SdlAdapter {
         InputEvent::GamepadAxis(
           *which as usize,
           mapped,
-          self.to_axis_value(&axis, value)
+          self.to_axis_value(axis, value)
         )
       ),
       Err(e) => Err(e)
@@ -182,10 +184,7 @@ impl SdlAdapter {
 
   // Returns whether the given SDL axis is a trigger.
   fn is_trigger(&self, axis: &Axis) -> bool {
-    return match axis {
-      Axis::TriggerLeft | Axis::TriggerRight => true,
-      _ => false
-    }
+    return matches!(axis, Axis::TriggerLeft | Axis::TriggerRight);
   }
 }
 
@@ -207,25 +206,25 @@ impl InputAdapter for SdlAdapter {
           if self.is_trigger(&axis) {
             match self.to_trigger_event(&which, &axis, &value) {
               Ok(adapted) => events.push(adapted),
-              Err(_) => ()
+              Err(_) => self.dropped += 1
             }
           } else {
             match self.to_axis_event(&which, &axis, &value) {
               Ok(adapted) => events.push(adapted),
-              Err(_) => ()
+              Err(_) => self.dropped += 1
             }
           }
         },
         Event::ControllerButtonDown { timestamp: _, which, button } => {
           match self.to_button_event(&which, &button, true) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         Event::ControllerButtonUp {timestamp: _, which, button } => {
           match self.to_button_event(&which, &button, false) {
             Ok(adapted) => events.push(adapted),
-            Err(_) => ()
+            Err(_) => self.dropped += 1
           }
         },
         _ => ()
@@ -237,4 +236,21 @@ impl InputAdapter for SdlAdapter {
   fn is_connected(&mut self, gamepad_id: &usize) -> bool {
     return self.gamepads.contains_key(&(*gamepad_id as u32));
   }
+
+  fn get_devices(&mut self) -> Vec<(usize, String)> {
+    let mut devices: Vec<(usize, String)> = vec!();
+    for (which, gamepad) in &self.gamepads {
+      devices.push((*which as usize, gamepad.name()));
+    }
+    devices.sort();
+    return devices;
+  }
+
+  fn get_name(&self) -> &str {
+    return "SDL";
+  }
+
+  fn get_dropped(&self) -> &u64 {
+    return &self.dropped;
+  }
 }
@@ -5,10 +5,16 @@ use crate::input::adapter::common::{
 };
 
 use serde::{Serialize, Deserialize};
-use strum_macros::EnumString;
+use std::collections::HashMap;
+use strum_macros::{
+  EnumIter,
+  EnumString
+};
 
 // Represents the different Switch controllers that can be emulated.
-#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, EnumString)]
+#[derive(
+  Copy, Clone, Debug, PartialEq, Serialize, Deserialize, EnumString, EnumIter
+)]
 pub enum SwitchPad {
   Disconnected,
   ProController,
@@ -23,6 +29,7 @@ pub enum SwitchPad {
 }
 
 // Represents all the different buttons on a Switch controller.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum SwitchButton {
   A,
   B,
@@ -55,6 +62,24 @@ pub enum SwitchButton {
 }
 
 impl SwitchButton {
+  // Returns every Switch button, in the order of their bits.
+  pub fn all() -> Vec<SwitchButton> {
+    return vec!(
+      Self::A, Self::B, Self::X, Self::Y, Self::LST, Self::RST, Self::L,
+      Self::R, Self::ZL, Self::ZR, Self::Plus, Self::Minus, Self::DL, Self::DU,
+      Self::DR, Self::DD, Self::LL, Self::LU, Self::LR, Self::LD, Self::RL,
+      Self::RU, Self::RR, Self::RD, Self::SLL, Self::SRL, Self::SLR, Self::SRR
+    );
+  }
+
+  // Decodes a keyout into the Switch buttons that are pressed in it.
+  pub fn decode(keyout: &i32) -> Vec<SwitchButton> {
+    return Self::all()
+      .into_iter()
+      .filter(|button| keyout & button.value() != 0)
+      .collect();
+  }
+
   // Returns the bit corresponding to this button.
   pub fn value(&self) -> i32 {
     // TODO: Home button cannot be emulated until libnx adds support.
@@ -113,25 +138,33 @@ impl SwitchButton {
       InputButton::Select => Ok(Self::Minus),
       
       InputButton::North => match switch_pad {
-        SwitchPad::Disconnected => Err(format!("No map for disconnected pad.")),
+        SwitchPad::Disconnected => Err(
+          "No map for disconnected pad.".to_string()
+        ),
         SwitchPad::ProController => Ok(Self::X),
         SwitchPad::JoyConLSide => Ok(Self::DR),
         SwitchPad::JoyConRSide => Ok(Self::Y)
       },
       InputButton::East => match switch_pad {
-        SwitchPad::Disconnected => Err(format!("No map for disconnected pad.")),
+        SwitchPad::Disconnected => Err(
+          "No map for disconnected pad.".to_string()
+        ),
         SwitchPad::ProController => Ok(Self::A),
         SwitchPad::JoyConLSide => Ok(Self::DD),
         SwitchPad::JoyConRSide => Ok(Self::X)
       },
       InputButton::South => match switch_pad {
-        SwitchPad::Disconnected => Err(format!("No map for disconnected pad.")),
+        SwitchPad::Disconnected => Err(
+          "No map for disconnected pad.".to_string()
+        ),
         SwitchPad::ProController => Ok(Self::B),
         SwitchPad::JoyConLSide => Ok(Self::DL),
         SwitchPad::JoyConRSide => Ok(Self::A)
       },
       InputButton::West => match switch_pad {
-        SwitchPad::Disconnected => Err(format!("No map for disconnected pad.")),
+        SwitchPad::Disconnected => Err(
+          "No map for disconnected pad.".to_string()
+        ),
         SwitchPad::ProController => Ok(Self::Y),
         SwitchPad::JoyConLSide => Ok(Self::DU),
         SwitchPad::JoyConRSide => Ok(Self::B)
@@ -147,12 +180,18 @@ impl SwitchButton {
  * field, which is updated through bitwise operations. This could be done
  * through a map of buttons to booleans instead, but this is more true to the
  * original client and, in all honesty, is way more compact.
+ *
+ * Buttons in "mapping" press the Switch button they're mapped to, instead of
+ * the one they'd usually press for this pad's type. Each stick also ignores
+ * any movement within its deadzone in "deadzones", given as (left, right).
  */
 pub struct EmulatedPad {
   switch_pad: SwitchPad,
   keyout: i32,
   left: (i32, i32),
-  right: (i32, i32)
+  right: (i32, i32),
+  mapping: HashMap<InputButton, SwitchButton>,
+  deadzones: (f32, f32)
 }
 
 impl EmulatedPad {
@@ -165,7 +204,9 @@ impl EmulatedPad {
       switch_pad: SwitchPad::Disconnected,
       keyout: 0,
       left: (0, 0),
-      right: (0, 0)
+      right: (0, 0),
+      mapping: HashMap::new(),
+      deadzones: (0.0, 0.0)
     }
   }
 
@@ -186,14 +227,56 @@ impl EmulatedPad {
     return &self.right;
   }
 
+  /**
+   * Sets the button mapping and stick deadzones of this pad, which apply to
+   * any inputs from then on.
+   */
+  pub fn configure(
+    &mut self, mapping: HashMap<InputButton, SwitchButton>,
+    deadzones: (f32, f32)
+  ) -> () {
+    self.mapping = mapping;
+    self.deadzones = deadzones;
+  }
+
   // Connects this pad by assigning a Switch pad.
   pub fn connect(&mut self, switch_pad: SwitchPad) -> () {
     self.switch_pad = switch_pad;
   }
 
-  // Disconnects this pad by setting its Switch pad type to Disconnected.
+  /**
+   * Disconnects this pad by setting its Switch pad type to Disconnected. Its
+   * inputs are neutralized too, so nothing stays pressed if it's reconnected.
+   */
   pub fn disconnect(&mut self) -> () {
     self.switch_pad = SwitchPad::Disconnected;
+    self.neutralize();
+  }
+
+  /**
+   * Returns this pad to a neutral state, where no buttons are pressed and both
+   * sticks are centered. The Switch pad type is left untouched.
+   */
+  pub fn neutralize(&mut self) -> () {
+    self.keyout = 0;
+    self.left = (0, 0);
+    self.right = (0, 0);
+  }
+
+  /**
+   * Sets this pad's state directly, where keyout is the sum of the values of
+   * every pressed Switch button, and each stick is given as (x, y) from -32767
+   * to 32767. Like input events, this does nothing while the pad is
+   * disconnected.
+   */
+  pub fn set_state(
+    &mut self, keyout: i32, left: (i32, i32), right: (i32, i32)
+  ) -> () {
+    if self.switch_pad != SwitchPad::Disconnected {
+      self.keyout = keyout;
+      self.left = left;
+      self.right = right;
+    }
   }
 
   // Updates this pad using an input event.
@@ -209,22 +292,33 @@ impl EmulatedPad {
   // Updates this pad's keyout.
   fn update_keyout(&mut self, button: &InputButton, value: &f32) -> () {
     if self.switch_pad != SwitchPad::Disconnected {
-      match &SwitchButton::map_button(
-        button,
-        &self.switch_pad
-      ) {
-        Ok(switch_button) => self.set_del_bit(
-          &switch_button.value(),
-          &(*value as i32)
-        ),
-        Err(_) => ()
+      let mapped: Result<SwitchButton, String> =
+        match self.mapping.get(button) {
+          Some(switch_button) => Ok(*switch_button),
+          None => SwitchButton::map_button(button, &self.switch_pad)
+        };
+      if let Ok(switch_button) = &mapped {
+        self.set_del_bit(&switch_button.value(), &(*value as i32));
       }
     }
   }
 
-  // Updates the stick values for an axis.
+  /**
+   * Updates the stick values for an axis. Values within the stick's deadzone
+   * are treated as 0, and the rest are scaled so the stick still goes from
+   * the edge of the deadzone all the way to the end.
+   */
   fn update_axis(&mut self, axis: &InputAxis, value: &f32) -> () {
-    let converted: i32 = (*value * 32767.0) as i32;
+    let deadzone: f32 = match axis {
+      InputAxis::LeftX | InputAxis::LeftY => self.deadzones.0,
+      InputAxis::RightX | InputAxis::RightY => self.deadzones.1
+    };
+    let scaled: f32 = if value.abs() < deadzone {
+      0.0
+    } else {
+      value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+    };
+    let converted: i32 = (scaled * 32767.0) as i32;
     match axis {
       InputAxis::LeftX => self.left.0 = converted,
       InputAxis::LeftY => self.left.1 = converted,
@@ -239,9 +333,9 @@ impl EmulatedPad {
    */
   fn set_del_bit(&mut self, bit: &i32, value: &i32) -> () {
     if value != &0 {
-      self.keyout = self.keyout | bit;
+      self.keyout |= bit;
     } else {
-      self.keyout = self.keyout & !bit;
+      self.keyout &= !bit;
     }
   }
 }
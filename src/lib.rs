@@ -0,0 +1,108 @@
+/*!
+ * The client as a library, so it can be embedded in other tools. The client-rs
+ * binary is itself just a command line consumer of this.
+ *
+ * There are two ways to embed a client:
+ * - ClientModel keeps track of four emulated gamepads and sends their states
+ *   to the input server. Gamepads can be fed InputEvents from any adapter in
+ *   input::adapter, or have their states set directly. Nothing is sent until
+ *   tick() is called, which should be done every controller::UPDATE_INTERVAL.
+ * - ClientController is the whole client, with commands, a config, slots and
+ *   an input adapter. It's driven by calling update() on the same interval,
+ *   until update() returns the status the client exited with.
+ *
+ * ```no_run
+ * use client_rs::{
+ *   input::switch::{SwitchButton, SwitchPad},
+ *   model::ClientModel
+ * };
+ *
+ * let mut model: ClientModel = ClientModel::bind("0.0.0.0:8001").unwrap();
+ * model.set_server_ip(&"192.168.1.2".to_string());
+ * model.connect_pad(&0, &SwitchPad::ProController);
+ * model.set_pad_state(&0, SwitchButton::A.value(), (0, 32767), (0, 0));
+ * model.tick().unwrap();
+ * ```
+ *
+ * A controller is driven until it exits, and then gives back the status to
+ * exit with. Nothing here ends the process on its own.
+ *
+ * ```no_run
+ * # #[cfg(all(feature = "cli", feature = "gilrs"))]
+ * # fn main() {
+ * use client_rs::{
+ *   config::ConfigOverrides,
+ *   controller::{
+ *     ClientController,
+ *     UPDATE_INTERVAL
+ *   },
+ *   input::adapter::gilrs::GilrsAdapter,
+ *   model::ClientModel,
+ *   view::cli::CLIView
+ * };
+ * use std::{
+ *   path::PathBuf,
+ *   thread
+ * };
+ *
+ * let mut controller: ClientController = ClientController::new(
+ *   ClientModel::new().unwrap(),
+ *   Box::new(CLIView::headless()),
+ *   Box::new(GilrsAdapter::new()),
+ *   PathBuf::from("config.toml"),
+ *   ConfigOverrides::default()
+ * );
+ * controller.initialize().unwrap();
+ * controller.execute("start");
+ * let code: i32 = loop {
+ *   if let Some(code) = controller.update() {
+ *     break code;
+ *   }
+ *   thread::sleep(UPDATE_INTERVAL);
+ * };
+ * println!("The client exited with {}.", code);
+ * # }
+ * # #[cfg(not(all(feature = "cli", feature = "gilrs")))]
+ * # fn main() {}
+ * ```
+ *
+ * Anything that needs a system library is behind a cargo feature, all of which
+ * are on by default. Embedders that only need the model or their own view and
+ * adapter can turn them off with default-features = false.
+ * - cli and tui are the views in view::cli and view::tui.
+ * - remote is the JSON-RPC and HTTP servers in remote::rpc and remote::http.
+ * - sdl, gilrs and multiinput are the adapters of the same names in
+ *   input::adapter.
+ */
+
+// Explicit returns, unit return types, field names, matches on Results, and
+// constructors that take every field are how this crate is written, so clippy
+// isn't asked to flag them.
+#![allow(
+  clippy::needless_return,
+  clippy::unused_unit,
+  clippy::redundant_field_names,
+  clippy::question_mark,
+  clippy::new_without_default,
+  clippy::too_many_arguments
+)]
+
+pub mod command;
+pub mod config;
+pub mod controller;
+pub mod input;
+pub mod model;
+pub mod outage;
+pub mod remote;
+pub mod stats;
+pub mod status;
+pub mod view;
+
+#[macro_use(c)]
+extern crate cute;
+
+#[macro_use]
+extern crate log;
+
+#[macro_use]
+extern crate structure;
@@ -0,0 +1,91 @@
+use flexi_logger::{
+  detailed_format,
+  Cleanup,
+  Criterion,
+  FileSpec,
+  Logger,
+  LoggerHandle,
+  Naming
+};
+use std::path::{
+  Path,
+  PathBuf
+};
+
+// The level every module logs at unless told otherwise.
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+// How big a log file can get before it's rotated, in bytes.
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+// How many rotated log files are kept around.
+const KEPT_LOGS: usize = 4;
+
+// Returns the folder logs are kept in, which is next to the config.
+pub fn default_log_dir(config_path: &Path) -> PathBuf {
+  return match config_path.parent() {
+    Some(dir) => dir.join("logs"),
+    None => PathBuf::from("logs")
+  }
+}
+
+/**
+ * Starts logging to a rotating file in the given folder. Diagnostics only ever
+ * go to the file, so they never get mixed up with messages meant for users.
+ *
+ * The spec decides which levels are logged, and can be set per module, like
+ * 'info, client_rs::input=trace'. Without a spec, the RUST_LOG environment
+ * variable is used, and then DEFAULT_LOG_LEVEL.
+ *
+ * Logging stops once the returned handle is dropped.
+ */
+pub fn init(spec: Option<&str>, dir: &Path) -> Result<LoggerHandle, String> {
+  let logger: Logger = match spec {
+    Some(spec) => match Logger::try_with_str(spec) {
+      Ok(logger) => logger,
+      Err(e) => return Err(format!("'{}' isn't a valid log level: {}", spec, e))
+    },
+    None => match Logger::try_with_env_or_str(DEFAULT_LOG_LEVEL) {
+      Ok(logger) => logger,
+      Err(e) => return Err(format!("RUST_LOG isn't a valid log level: {}", e))
+    }
+  };
+  return logger
+    .log_to_file(
+      FileSpec::default()
+        .directory(dir)
+        .basename("client-rs")
+        .suppress_timestamp()
+    )
+    .format_for_files(detailed_format)
+    .append()
+    .rotate(
+      Criterion::Size(MAX_LOG_SIZE),
+      Naming::Numbers,
+      Cleanup::KeepLogFiles(KEPT_LOGS)
+    )
+    .start()
+    .map_err(|e| format!("Error occurred while starting the log: {}", e));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn logs_kept_next_to_config() {
+    assert_eq!(
+      default_log_dir(Path::new("/home/me/client-rs/config.toml")),
+      PathBuf::from("/home/me/client-rs/logs")
+    );
+    assert_eq!(default_log_dir(Path::new("")), PathBuf::from("logs"));
+  }
+
+  #[test]
+  fn invalid_level_refused() {
+    let e: String = init(Some("client_rs=loud"), Path::new("logs"))
+      .err()
+      .unwrap();
+    assert!(e.starts_with("'client_rs=loud' isn't a valid log level"), "{}", e);
+  }
+}
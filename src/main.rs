@@ -1,47 +1,351 @@
-pub mod config;
-pub mod controller;
-pub mod input;
-pub mod model;
-pub mod view;
-
-use crate::{
-  input::adapter::sdl::SdlAdapter,
+// Explicit returns, unit return types, field names, matches on Results, and
+// constructors that take every field are how this crate is written, so clippy
+// isn't asked to flag them.
+#![allow(
+  clippy::needless_return,
+  clippy::unused_unit,
+  clippy::redundant_field_names,
+  clippy::question_mark,
+  clippy::new_without_default,
+  clippy::too_many_arguments
+)]
+
+mod args;
+mod logging;
+
+use crate::args::{
+  Args,
+  Backend
+};
+use client_rs::{
+  command,
+  input::adapter::common::InputAdapter,
   model::ClientModel,
   controller::{
-    ClientController
+    ClientController,
+    UPDATE_INTERVAL
   },
-  view::cli::CLIView
+  view::{
+    cli::CLIView,
+    common::ClientView
+  }
 };
+#[cfg(feature = "gilrs")]
+use client_rs::input::adapter::gilrs::GilrsAdapter;
+#[cfg(feature = "multiinput")]
+use client_rs::input::adapter::multiinput::MultiInputAdapter;
+#[cfg(feature = "sdl")]
+use client_rs::input::adapter::sdl::SdlAdapter;
+#[cfg(feature = "remote")]
+use client_rs::remote::{
+  self,
+  event::EventBus,
+  http,
+  rpc
+};
+#[cfg(feature = "tui")]
+use client_rs::view::tui::TUIView;
+use clap::Parser;
+use flexi_logger::LoggerHandle;
 use crossbeam_channel::{tick, select};
-use std::time;
-
-#[macro_use(c)]
-extern crate cute;
+use std::{
+  path::{
+    Path,
+    PathBuf
+  },
+  process,
+  sync::{
+    Arc,
+    atomic::AtomicBool
+  }
+};
+#[cfg(unix)]
+use std::{
+  io::{
+    stdin,
+    IsTerminal
+  },
+  os::raw::c_int
+};
+#[cfg(not(unix))]
+use std::{
+  sync::OnceLock,
+  thread,
+  time::Duration
+};
+#[cfg(not(unix))]
+use windows_sys::Win32::{
+  Foundation::BOOL,
+  System::Console::{
+    SetConsoleCtrlHandler,
+    CTRL_CLOSE_EVENT,
+    CTRL_LOGOFF_EVENT,
+    CTRL_SHUTDOWN_EVENT
+  }
+};
 
 #[macro_use]
-extern crate structure;
+extern crate log;
+
+/**
+ * Constructs the view to use. The full-screen terminal view is used when the
+ * client is run with --tui, and the plain command line view otherwise, which is
+ * easier to use from scripts. With --headless, stdin isn't read at all.
+ */
+fn make_view(
+  args: &Args, num_slots: usize, config_path: &Path
+) -> Result<Box<dyn ClientView>, String> {
+  if *args.get_headless() {
+    return Ok(Box::new(CLIView::headless()));
+  } else if *args.get_tui() {
+    #[cfg(feature = "tui")]
+    return match TUIView::new() {
+      Ok(view) => Ok(Box::new(view)),
+      Err(e) => Err(e)
+    };
+    #[cfg(not(feature = "tui"))]
+    return Err(missing("--tui", "tui"));
+  } else {
+    return Ok(
+      Box::new(CLIView::new(&command::names(), num_slots, config_path))
+    );
+  }
+}
+
+/**
+ * Constructs the input adapter for the backend chosen with --backend. Backends
+ * the client was built without can still be chosen, but only give an error.
+ */
+fn make_input_adapter(
+  backend: &Backend
+) -> Result<Box<dyn InputAdapter>, String> {
+  return match backend {
+    #[cfg(feature = "sdl")]
+    Backend::Sdl => Ok(Box::new(SdlAdapter::new())),
+    #[cfg(feature = "gilrs")]
+    Backend::Gilrs => Ok(Box::new(GilrsAdapter::new())),
+    #[cfg(feature = "multiinput")]
+    Backend::MultiInput => Ok(Box::new(MultiInputAdapter::new())),
+    #[allow(unreachable_patterns)]
+    _ => {
+      let name: String = format!("{:?}", backend).to_lowercase();
+      Err(missing(&format!("the {} backend", name), &name))
+    }
+  }
+}
+
+// Describes something the client was built without, and how to build it in.
+fn missing(what: &str, feature: &str) -> String {
+  return format!(
+    "This build of the client doesn't support {}. Rebuild it with the \"{}\" \
+    feature to use it.",
+    what, feature
+  );
+}
+
+/**
+ * Raises the exit flag whenever the client receives SIGINT or SIGTERM. If
+ * either of those is received again while the client is already exiting, it
+ * closes right away instead.
+ *
+ * SIGHUP raises the reload flag, unless stdin is a terminal. Then SIGHUP means
+ * the terminal was closed, so it's treated like SIGTERM instead, and gamepads
+ * are still cleaned up.
+ */
+#[cfg(unix)]
+fn handle_signals(
+  reload: Arc<AtomicBool>, exit: Arc<AtomicBool>
+) -> Result<(), String> {
+  let mut exits: Vec<(c_int, &str)> = vec!(
+    (signal_hook::consts::SIGINT, "SIGINT"),
+    (signal_hook::consts::SIGTERM, "SIGTERM")
+  );
+  if stdin().is_terminal() {
+    exits.push((signal_hook::consts::SIGHUP, "SIGHUP"));
+  } else if let Err(e) = signal_hook::flag::register(
+    signal_hook::consts::SIGHUP, reload
+  ) {
+    return Err(format!("Couldn't listen for SIGHUP: {}", e));
+  }
+  for (signal, name) in exits {
+    // This has to check the flag before it's raised, so the order matters.
+    let registered = signal_hook::flag::register_conditional_shutdown(
+      signal, 1, exit.clone()
+    ).and_then(|_| signal_hook::flag::register(signal, exit.clone()));
+    if let Err(e) = registered {
+      return Err(format!("Couldn't listen for {}: {}", name, e));
+    }
+  }
+  return Ok(());
+}
+
+/**
+ * There's no SIGHUP outside of Unix, so the config is only reloaded on change.
+ * Ctrl-C and Ctrl-Break raise the exit flag, though, and pressing either again
+ * while the client is already exiting closes it right away.
+ *
+ * Closing the console (or logging off or shutting down) raises the exit flag
+ * too, through handle_close().
+ */
+#[cfg(not(unix))]
+fn handle_signals(
+  _reload: Arc<AtomicBool>, exit: Arc<AtomicBool>
+) -> Result<(), String> {
+  let _ = CLOSE_EXIT.set(exit.clone());
+  if let Err(e) = ctrlc::set_handler(move || {
+    if exit.swap(true, std::sync::atomic::Ordering::Relaxed) {
+      std::process::exit(1);
+    }
+  }) {
+    return Err(format!("Couldn't listen for Ctrl-C: {}", e));
+  }
+  // Handlers added later are called first, so this sees closes before ctrlc.
+  if unsafe { SetConsoleCtrlHandler(Some(handle_close), 1) } == 0 {
+    return Err(
+      format!(
+        "Couldn't listen for the console closing: {}",
+        std::io::Error::last_os_error()
+      )
+    );
+  }
+  return Ok(());
+}
+
+// The exit flag that handle_close() raises.
+#[cfg(not(unix))]
+static CLOSE_EXIT: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/**
+ * How long handle_close() waits for the client to clean up. Windows ends the
+ * client about 5 seconds after the console is closed no matter what.
+ */
+#[cfg(not(unix))]
+const CLOSE_WAIT: Duration = Duration::from_millis(4500);
+
+/**
+ * Raises the exit flag when the console is closed, then keeps the client
+ * alive while it cleans up. Windows ends the client as soon as this returns,
+ * so this only returns once the wait is up. The client usually ends itself
+ * before then, once it's done.
+ */
+#[cfg(not(unix))]
+unsafe extern "system" fn handle_close(ctrl_type: u32) -> BOOL {
+  match ctrl_type {
+    CTRL_CLOSE_EVENT | CTRL_LOGOFF_EVENT | CTRL_SHUTDOWN_EVENT => {
+      if let Some(exit) = CLOSE_EXIT.get() {
+        exit.store(true, std::sync::atomic::Ordering::Relaxed);
+      }
+      thread::sleep(CLOSE_WAIT);
+      return 1;
+    },
+    // Anything else, like Ctrl-C, is left to ctrlc.
+    _ => return 0
+  }
+}
+
+/**
+ * Starts whichever remote servers were asked for with --rpc and --http. Both
+ * end up as calls to the controller.
+ */
+#[cfg(feature = "remote")]
+fn serve_remote(args: &Args, controller: &mut ClientController) -> () {
+  let (handle, receiver) = remote::channel();
+  controller.listen(receiver);
+  if let Some(address) = args.get_rpc() {
+    controller.report(rpc::serve(address, handle.clone()));
+  }
+  if let Some(address) = args.get_http() {
+    let events: EventBus = controller.get_events();
+    controller.report(http::serve(address, handle, events));
+  }
+}
+
+// Without the servers, asking for either only gives an error.
+#[cfg(not(feature = "remote"))]
+fn serve_remote(args: &Args, controller: &mut ClientController) -> () {
+  if args.get_rpc().is_some() {
+    controller.report(Err(missing("--rpc", "remote")));
+  }
+  if args.get_http().is_some() {
+    controller.report(Err(missing("--http", "remote")));
+  }
+}
+
+/**
+ * Tells the user why the client couldn't start, then exits with an error. Any
+ * view should already be closed, so the message isn't lost with it.
+ */
+fn fail(e: String) -> ! {
+  error!("Couldn't start: {}", e);
+  eprintln!("{}", e);
+  process::exit(1);
+}
 
 fn main() -> () {
+  let args: Args = Args::parse();
+  let config_path: PathBuf = args.config_path();
+  // Logging stops when this is dropped, so it's kept around until the end.
+  let logger: Result<LoggerHandle, String> = logging::init(
+    args.get_log_level().as_deref(), &args.log_dir()
+  );
+  info!("Starting client-rs {}.", env!("CARGO_PKG_VERSION"));
   match ClientModel::new() {
     Ok(model) => {
+      let input_adapter: Box<dyn InputAdapter> = match make_input_adapter(
+        args.get_backend()
+      ) {
+        Ok(input_adapter) => input_adapter,
+        Err(e) => fail(e)
+      };
+      let view: Box<dyn ClientView> = match make_view(
+        &args, model.num_pads(), &config_path
+      ) {
+        Ok(view) => view,
+        Err(e) => fail(e)
+      };
       let mut controller: ClientController = ClientController::new(
         model,
-        Box::new(CLIView::new()),
-        Box::new(SdlAdapter::new())
+        view,
+        input_adapter,
+        config_path,
+        args.overrides()
       );
-      let ticks = tick(time::Duration::from_secs_f32(1.0 / 60.0));
+      if let Err(e) = handle_signals(
+        controller.get_reload_flag(), controller.get_exit_flag()
+      ) {
+        controller.close();
+        fail(e);
+      }
+      let ticks = tick(UPDATE_INTERVAL);
 
       match controller.initialize() {
-        Ok(_) => loop {
-          select! {
-            recv(ticks) -> _ => {
-              controller.update();
+        Ok(_) => {
+          if let Err(e) = &logger {
+            controller.report(Err(e.clone()));
+          }
+          serve_remote(&args, &mut controller);
+          if *args.get_save() {
+            controller.save_overrides();
+          }
+          if *args.get_autostart() {
+            controller.execute("start");
+          }
+          loop {
+            select! {
+              recv(ticks) -> _ => {
+                if let Some(code) = controller.update() {
+                  process::exit(code);
+                }
+              }
             }
           }
         },
-        Err(e) => panic!("{}", e)
+        Err(e) => {
+          controller.close();
+          fail(e);
+        }
       }
     },
-    Err(e) => panic!("{}", e)
+    Err(e) => fail(format!("Couldn't open a socket on port 8000: {}", e))
   }
 }
@@ -1,19 +1,34 @@
 use crate::{
   input::{
     adapter::common::{
+      InputButton,
       InputEvent
     },
     switch::{
+      SwitchButton,
       SwitchPad,
       EmulatedPad
     }
   }
 };
 use std::{
+  collections::HashMap,
   net::UdpSocket,
   time
 };
 
+/**
+ * Represents a cleanup that's underway. The same packet, where every gamepad is
+ * disconnected, is sent to the same address until the cleanup's over, even if
+ * the model's gamepads or server change in the meantime.
+ */
+struct Cleanup {
+  address: String,
+  packet: Vec<u8>,
+  start: time::Instant,
+  duration: time::Duration
+}
+
 /**
  * Represents a model for an input client. The model is responsible for keeping
  * track of the emulated gamepads and sending their states over to the input
@@ -21,23 +36,43 @@ use std::{
  */
 pub struct ClientModel {
   server_ip: String,
+  server_port: u16,
   sock: UdpSocket,
   pads: Vec<EmulatedPad>,
+  packets_sent: u64,
+  packets_failed: u64,
+  last_sent: Option<time::Instant>,
+  cleanup: Option<Cleanup>
 }
 
 impl ClientModel {
   /**
    * Constructs a model where the socket is bound to port 8000 and all emulated
    * gamepads are initially set to Disconnected. The server IP is also initially
-   * blank as well, but this can be updated through its respective setter.
+   * blank as well, but this can be updated through its respective setter. The
+   * server is assumed to listen on port 8000 until told otherwise.
    */
   pub fn new() -> Result<ClientModel, String> {
-    return match UdpSocket::bind("0.0.0.0:8000") {
+    return ClientModel::bind("0.0.0.0:8000");
+  }
+
+  /**
+   * Constructs a model like new() does, except the socket is bound to the given
+   * address. This lets a client be embedded alongside another one, since only
+   * one socket can be bound to a port at a time.
+   */
+  pub fn bind(address: &str) -> Result<ClientModel, String> {
+    return match UdpSocket::bind(address) {
       Ok(sock) => Ok(
         ClientModel {
           server_ip: "".to_string(),
+          server_port: 8000,
           sock: sock,
-          pads: c![EmulatedPad::new(), for _i in 0..4]
+          pads: c![EmulatedPad::new(), for _i in 0..4],
+          packets_sent: 0,
+          packets_failed: 0,
+          last_sent: None,
+          cleanup: None
         }
       ),
       Err(e) => Err(format!("{}", e))
@@ -54,6 +89,37 @@ impl ClientModel {
     self.server_ip = server_ip.to_string();
   }
 
+  // Server Port Getter
+  pub fn get_server_port(&self) -> &u16 {
+    return &self.server_port;
+  }
+
+  // Server Port Setter
+  pub fn set_server_port(&mut self, server_port: &u16) -> () {
+    self.server_port = *server_port;
+  }
+
+  // Returns the emulated gamepad at a given index.
+  pub fn get_pad(&self, i: &usize) -> &EmulatedPad {
+    return &self.pads[*i];
+  }
+
+  // Returns how many packets were sent since the count was last reset.
+  pub fn get_packets_sent(&self) -> &u64 {
+    return &self.packets_sent;
+  }
+
+  // Returns how many packets failed to send since the count was last reset.
+  pub fn get_packets_failed(&self) -> &u64 {
+    return &self.packets_failed;
+  }
+
+  // Resets the counts of packets sent and failed.
+  pub fn reset_packet_counts(&mut self) -> () {
+    self.packets_sent = 0;
+    self.packets_failed = 0;
+  }
+
   // Returns the number of emulated gamepads in this model.
   pub fn num_pads(&self) -> usize {
     return self.pads.len();
@@ -69,6 +135,29 @@ impl ClientModel {
     self.pads[*i].disconnect();
   }
 
+  /**
+   * Neutralizes a gamepad in this model, releasing all of its buttons and
+   * centering its sticks while keeping it connected.
+   */
+  pub fn neutralize_pad(&mut self, i: &usize) -> () {
+    self.pads[*i].neutralize();
+  }
+
+  // Neutralizes every gamepad in this model.
+  pub fn neutralize_all(&mut self) -> () {
+    for pad in &mut self.pads {
+      pad.neutralize();
+    }
+  }
+
+  // Sets the button mapping and stick deadzones of a gamepad in this model.
+  pub fn configure_pad(
+    &mut self, i: &usize, mapping: HashMap<InputButton, SwitchButton>,
+    deadzones: (f32, f32)
+  ) -> () {
+    self.pads[*i].configure(mapping, deadzones);
+  }
+
   // Updates a target gamepad in this model using an input event.
   pub fn update_pad(&mut self, i: &usize, event: &InputEvent) -> () {
     self.pads[*i].update(event);
@@ -84,44 +173,124 @@ impl ClientModel {
     self.pads[*i].connect(*switch_pad);
   }
 
+  /**
+   * Sets the state of a target gamepad in this model directly, instead of
+   * through input events. See EmulatedPad::set_state() for the details.
+   */
+  pub fn set_pad_state(
+    &mut self, i: &usize, keyout: i32, left: (i32, i32), right: (i32, i32)
+  ) -> () {
+    self.pads[*i].set_state(keyout, left, right);
+  }
+
+  /**
+   * Does what this model should do on every update, which should happen at a
+   * fixed time interval. That's sending the next packet of the cleanup if one's
+   * underway, and sending the current emulated pad states otherwise.
+   */
+  pub fn tick(&mut self) -> Result<(), String> {
+    if self.is_cleaning_up() {
+      return match self.update_cleanup() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e)
+      }
+    } else {
+      return self.update_server();
+    }
+  }
+
   // Sends the current emulated pad states to the input server.
-  pub fn update_server(&self) -> Result<(), String> {
+  pub fn update_server(&mut self) -> Result<(), String> {
+    let start: time::Instant = time::Instant::now();
     match self.sock.send_to(
       &PackedData::new(&self.pads, 4).to_bytes(),
-      format!("{}:8000", self.server_ip)
+      format!("{}:{}", self.server_ip, self.server_port)
     ) {
-      Ok(_) => Ok(()),
-      Err(e) => return Err(
-        format!( 
-          "The following error occurred while updating the server: {}.", e
-        )
-      )
+      Ok(_) => {
+        self.packets_sent += 1;
+        trace!(
+          "Sent packet {} in {:?}, {:?} after the last one.",
+          self.packets_sent,
+          start.elapsed(),
+          self.last_sent.map(|last_sent| start - last_sent)
+        );
+        self.last_sent = Some(start);
+        return Ok(());
+      },
+      Err(e) => {
+        self.packets_failed += 1;
+        return Err(
+          format!( 
+            "The following error occurred while updating the server: {}.", e
+          )
+        );
+      }
     }
   }
 
   /**
-   * Disconnects all connected gamepads through an unfortunately-brute-force
-   * method.
+   * Starts disconnecting all connected gamepads through an
+   * unfortunately-brute-force method, which is repeatedly telling the server
+   * that they're disconnected for the given duration. Packets are only sent by
+   * update_cleanup(), so nothing is blocked in the meantime.
    *
    * Without an established protocol, this seems to be the only way to
    * disconnect everything reliably.
    */
-  pub fn cleanup(&mut self) -> Result<String, String> {
+  pub fn start_cleanup(&mut self, duration: time::Duration) -> () {
     for pad in &mut self.pads {
       pad.disconnect();
     }
-    // self.pads = c![EmulatedPad::new(), for _i in 0..4];
-    let start: time::Instant = time::Instant::now();
-    while start.elapsed().as_millis() < 3000 {
-      match self.sock.send_to(
-        &PackedData::new(&self.pads, 4).to_bytes(),
-        format!("{}:8000", self.server_ip)
-      ) {
-        Err(e) => return Err(e.to_string()),
-        Ok(_) => ()
+    self.cleanup = Some(
+      Cleanup {
+        address: format!("{}:{}", self.server_ip, self.server_port),
+        packet: PackedData::new(&self.pads, 4).to_bytes(),
+        start: time::Instant::now(),
+        duration: duration
+      }
+    );
+  }
+
+  // Returns whether a cleanup is underway.
+  pub fn is_cleaning_up(&self) -> bool {
+    return self.cleanup.is_some();
+  }
+
+  // Returns how far along the cleanup is from 0 to 1, if one's underway.
+  pub fn get_cleanup_progress(&self) -> Option<f32> {
+    return self.cleanup.as_ref().map(|cleanup| {
+      if cleanup.duration.is_zero() {
+        return 1.0;
       }
+      return (
+        cleanup.start.elapsed().as_secs_f32() / cleanup.duration.as_secs_f32()
+      ).min(1.0);
+    });
+  }
+
+  /**
+   * Sends the next packet of the cleanup, if one's underway, which should be
+   * done at a fixed time interval. Returns whether the cleanup just finished.
+   *
+   * Every cleanup sends at least one packet, no matter how short it is.
+   */
+  pub fn update_cleanup(&mut self) -> Result<bool, String> {
+    let finished: bool = match &self.cleanup {
+      Some(cleanup) => {
+        if let Err(e) = self.sock.send_to(&cleanup.packet, &cleanup.address) {
+          self.cleanup = None;
+          return Err(
+            format!("The following error occurred while cleaning up: {}.", e)
+          );
+        }
+        cleanup.start.elapsed() >= cleanup.duration
+      },
+      None => return Ok(false)
+    };
+    if finished {
+      self.cleanup = None;
     }
-    return Ok("Gamepads should now be cleaned up.".to_string());
+    return Ok(finished);
   }
 }
 
@@ -176,7 +345,7 @@ fn to_switch_pad_value(switch_pad: &SwitchPad) -> i8 {
 
 impl PackedData {
   // Constructs a packed data struct just from a list of pads.
-  pub fn new(pads: &Vec<EmulatedPad>, connected: i8) -> PackedData {
+  pub fn new(pads: &[EmulatedPad], connected: i8) -> PackedData {
     return PackedData {
       magic: 0x3276,
       connected: connected as u16,
@@ -0,0 +1,126 @@
+use std::time;
+
+// How long to wait before the first retry.
+const MIN_DELAY: time::Duration = time::Duration::from_millis(100);
+
+// The longest that retries can be spaced out by.
+const MAX_DELAY: time::Duration = time::Duration::from_secs(2);
+
+/**
+ * Represents a stretch of time where sending to the server keeps failing, like
+ * while Wi-Fi is reconnecting. Sends are retried with exponential backoff,
+ * until the outage has lasted longer than the window it's given.
+ */
+pub struct Outage {
+  start: time::Instant,
+  window: time::Duration,
+  delay: time::Duration,
+  next_try: time::Instant,
+  failures: u32
+}
+
+impl Outage {
+  // Constructs an outage starting now, which can last for the given window.
+  pub fn new(window: time::Duration) -> Outage {
+    let now: time::Instant = time::Instant::now();
+    return Outage {
+      start: now,
+      window: window,
+      delay: MIN_DELAY,
+      next_try: now,
+      failures: 0
+    }
+  }
+
+  // Returns whether it's time to try sending again.
+  pub fn is_due(&self) -> bool {
+    return time::Instant::now() >= self.next_try;
+  }
+
+  // Records that a send failed, and waits longer before the next one.
+  pub fn fail(&mut self) -> () {
+    self.failures += 1;
+    self.next_try = time::Instant::now() + self.delay;
+    self.delay = (self.delay * 2).min(MAX_DELAY);
+  }
+
+  // Returns whether the outage has lasted too long to keep trying.
+  pub fn is_over(&self) -> bool {
+    return self.start.elapsed() >= self.window;
+  }
+
+  // Returns how long the outage has lasted so far.
+  pub fn elapsed(&self) -> time::Duration {
+    return self.start.elapsed();
+  }
+
+  pub fn get_failures(&self) -> &u32 {
+    return &self.failures;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn first_try_is_due() {
+    let outage: Outage = Outage::new(time::Duration::from_secs(10));
+    assert!(outage.is_due());
+    assert!(!outage.is_over());
+    assert_eq!(*outage.get_failures(), 0);
+  }
+
+  #[test]
+  fn failure_delays_next_try() {
+    let mut outage: Outage = Outage::new(time::Duration::from_secs(10));
+    outage.fail();
+    assert!(!outage.is_due());
+    assert_eq!(*outage.get_failures(), 1);
+    // Pretend the retry's time has come.
+    outage.next_try = time::Instant::now();
+    assert!(outage.is_due());
+  }
+
+  #[test]
+  fn backoff_doubles_each_failure() {
+    let mut outage: Outage = Outage::new(time::Duration::from_secs(10));
+    let mut delays: Vec<time::Duration> = vec!();
+    for _i in 0..7 {
+      delays.push(outage.delay);
+      outage.fail();
+    }
+    assert_eq!(
+      delays,
+      c![time::Duration::from_millis(ms), for ms in [
+        100, 200, 400, 800, 1600, 2000, 2000
+      ]]
+    );
+    assert_eq!(*outage.get_failures(), 7);
+  }
+
+  #[test]
+  fn backoff_capped_at_max_delay() {
+    let mut outage: Outage = Outage::new(time::Duration::from_secs(10));
+    for _i in 0..20 {
+      outage.fail();
+    }
+    assert_eq!(outage.delay, MAX_DELAY);
+    assert!(outage.next_try <= time::Instant::now() + MAX_DELAY);
+  }
+
+  #[test]
+  fn over_once_window_passes() {
+    let mut outage: Outage = Outage::new(time::Duration::from_millis(20));
+    assert!(!outage.is_over());
+    // Pretend the outage started a while ago.
+    outage.start = time::Instant::now() - time::Duration::from_millis(30);
+    assert!(outage.is_over());
+    assert!(outage.elapsed() >= time::Duration::from_millis(20));
+  }
+
+  #[test]
+  fn empty_window_over_right_away() {
+    assert!(Outage::new(time::Duration::from_millis(0)).is_over());
+  }
+}
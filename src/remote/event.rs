@@ -0,0 +1,102 @@
+use crossbeam_channel::{
+  unbounded,
+  Receiver,
+  Sender
+};
+use serde_json::{
+  json,
+  Value
+};
+use std::sync::{
+  Arc,
+  Mutex
+};
+
+/**
+ * Represents something that happened in the client that other programs may
+ * want to know about as soon as it happens.
+ * - Connected means a gamepad was put in a slot.
+ * - Disconnected means a gamepad was taken out of a slot. If the slot is being
+ *   held for the gamepad, held is true.
+ * - Error means something went wrong, with the same message shown to users.
+ */
+#[derive(Clone, Debug)]
+pub enum ClientEvent {
+  Connected {
+    slot: usize,
+    gamepad_id: usize,
+    name: String
+  },
+  Disconnected {
+    slot: usize,
+    gamepad_id: usize,
+    held: bool
+  },
+  Error(String)
+}
+
+impl ClientEvent {
+  // Returns the name of this kind of event.
+  pub fn get_name(&self) -> &'static str {
+    return match self {
+      ClientEvent::Connected { .. } => "connected",
+      ClientEvent::Disconnected { .. } => "disconnected",
+      ClientEvent::Error(_) => "error"
+    }
+  }
+
+  // Converts this event to JSON, so it can be read by other programs.
+  pub fn to_json(&self) -> Value {
+    return match self {
+      ClientEvent::Connected { slot, gamepad_id, name } => json!({
+        "slot": slot,
+        "gamepad_id": gamepad_id,
+        "name": name
+      }),
+      ClientEvent::Disconnected { slot, gamepad_id, held } => json!({
+        "slot": slot,
+        "gamepad_id": gamepad_id,
+        "held": held
+      }),
+      ClientEvent::Error(message) => json!({
+        "message": message
+      })
+    }
+  }
+}
+
+/**
+ * Represents a way to send events to everyone who's listening for them. Every
+ * clone publishes to the same subscribers.
+ */
+#[derive(Clone)]
+pub struct EventBus {
+  subscribers: Arc<Mutex<Vec<Sender<ClientEvent>>>>
+}
+
+impl EventBus {
+  pub fn new() -> EventBus {
+    return EventBus {
+      subscribers: Arc::new(Mutex::new(vec!()))
+    }
+  }
+
+  // Returns a receiver that gets every event published from now on.
+  pub fn subscribe(&self) -> Receiver<ClientEvent> {
+    let (sender, receiver) = unbounded();
+    if let Ok(mut subscribers) = self.subscribers.lock() {
+      subscribers.push(sender);
+    }
+    return receiver;
+  }
+
+  /**
+   * Sends an event to every subscriber. Subscribers that stopped listening are
+   * dropped along the way.
+   */
+  pub fn publish(&self, event: ClientEvent) -> () {
+    if let Ok(mut subscribers) = self.subscribers.lock() {
+      subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+  }
+}
@@ -0,0 +1,653 @@
+use crate::{
+  command::{
+    self,
+    Command
+  },
+  remote::{
+    self,
+    event::EventBus,
+    metrics,
+    RemoteCall,
+    RemoteHandle,
+    RemoteResponse
+  }
+};
+
+use crossbeam_channel::RecvTimeoutError;
+use serde_json::{
+  json,
+  Value
+};
+use std::{
+  io::{
+    Cursor,
+    Read,
+    Write
+  },
+  net::SocketAddr,
+  str::FromStr,
+  sync::Arc,
+  thread,
+  time
+};
+use tiny_http::{
+  Header,
+  Method,
+  Request,
+  Response,
+  Server
+};
+
+// How long an event stream can go quiet before a comment is sent to keep it up.
+const KEEP_ALIVE: time::Duration = time::Duration::from_secs(15);
+
+// The most bytes a request body can have.
+const MAX_BODY: u64 = 64 * 1024;
+
+// The header that requests can send the token in, instead of sending JSON.
+const TOKEN_HEADER: &str = "X-Client-Token";
+
+/**
+ * Represents what a request has to match to be answered at all. Browsers let
+ * any web page send some requests to localhost, so requests from pages are
+ * told apart from ones made by other programs:
+ * - hosts represent the names this server can be reached by. The Host header
+ *   has to be one of these, which stops pages from reaching the server through
+ *   a domain of their own (DNS rebinding).
+ * - origins represent the same, but as pages on this server. If a request has
+ *   an Origin header, it has to be one of these.
+ * - token represents a secret that's different every time the server starts.
+ *   Requests that change anything have to either send JSON or the token, since
+ *   a page can't do either without the browser asking this server first.
+ */
+struct Guard {
+  hosts: Vec<String>,
+  origins: Vec<String>,
+  token: String
+}
+
+impl Guard {
+  // Constructs a guard for a server listening on the given address.
+  fn new(addr: &SocketAddr, token: String) -> Guard {
+    let ip: String = match addr {
+      SocketAddr::V4(addr) => addr.ip().to_string(),
+      SocketAddr::V6(addr) => format!("[{}]", addr.ip())
+    };
+    let mut hosts: Vec<String> = vec!(
+      format!("{}:{}", ip, addr.port()),
+      format!("localhost:{}", addr.port())
+    );
+    // Port 80 is usually left out of the host.
+    if addr.port() == 80 {
+      hosts.push(ip);
+      hosts.push("localhost".to_string());
+    }
+    return Guard {
+      origins: c![format!("http://{}", host), for host in &hosts],
+      hosts: hosts,
+      token: token
+    }
+  }
+
+  // Returns why a request isn't allowed, if it isn't.
+  fn check(&self, request: &Request) -> Result<(), String> {
+    let allowed = |value: &str, list: &Vec<String>| -> bool {
+      return list.iter().any(|item| item.eq_ignore_ascii_case(value));
+    };
+    match header(request, "Host") {
+      Some(host) if allowed(host, &self.hosts) => (),
+      Some(host) => return Err(
+        format!("Requests for the host '{}' aren't answered.", host)
+      ),
+      None => return Err("Requests need a Host header.".to_string())
+    }
+    if let Some(origin) = header(request, "Origin") {
+      if !allowed(origin, &self.origins) {
+        return Err(
+          format!("Requests from the origin '{}' aren't answered.", origin)
+        );
+      }
+    }
+    if *request.method() == Method::Get || *request.method() == Method::Head {
+      return Ok(());
+    }
+    let is_json: bool = match header(request, "Content-Type") {
+      Some(content_type) => content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .eq_ignore_ascii_case("application/json"),
+      None => false
+    };
+    if is_json || header(request, TOKEN_HEADER) == Some(self.token.as_str()) {
+      return Ok(());
+    } else {
+      return Err(
+        format!(
+          "Requests that change anything need a Content-Type of \
+          application/json, or the {} header the client was started with.",
+          TOKEN_HEADER
+        )
+      );
+    }
+  }
+}
+
+// Makes a token that can't be guessed, from the OS's secure random numbers.
+fn make_token() -> Result<String, String> {
+  let mut bytes: [u8; 16] = [0; 16];
+  if let Err(e) = getrandom::getrandom(&mut bytes) {
+    return Err(format!("Couldn't make a token for the HTTP server: {}", e));
+  }
+  return Ok(c![format!("{:02x}", byte), for byte in bytes.iter()].concat());
+}
+
+// Returns the value of a request's header, if it has one.
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+  return request.headers()
+    .iter()
+    .find(|header| header.field.equiv(name))
+    .map(|header| header.value.as_str());
+}
+
+/**
+ * Starts an HTTP server in the background, which makes calls through the given
+ * handle and streams events from the given bus. The server only listens on
+ * localhost, and only answers requests made by other programs, not web pages.
+ */
+pub fn serve(
+  address: &str, handle: RemoteHandle, events: EventBus
+) -> Result<String, String> {
+  let addr: SocketAddr = match address.parse::<SocketAddr>() {
+    Ok(addr) => addr,
+    Err(_) => return Err(
+      format!(
+        "'{}' isn't an IP and port, like 127.0.0.1:8002, so the HTTP server \
+        can't listen on it.",
+        address
+      )
+    )
+  };
+  if let Err(e) = remote::check_local(&addr, "HTTP") {
+    return Err(e);
+  }
+  return match start(addr, handle, events) {
+    Ok((addr, token)) => Ok(
+      format!(
+        "Listening for HTTP requests on http://{}. Requests that change \
+        anything need to send JSON, or a {} header of {}.",
+        addr, TOKEN_HEADER, token
+      )
+    ),
+    Err(e) => Err(e)
+  }
+}
+
+/**
+ * Starts the server on an address that was already checked. Returns the
+ * address it's actually listening on, which only differs if the port was 0,
+ * along with the token it was started with.
+ */
+fn start(
+  addr: SocketAddr, handle: RemoteHandle, events: EventBus
+) -> Result<(SocketAddr, String), String> {
+  let token: String = match make_token() {
+    Ok(token) => token,
+    Err(e) => return Err(e)
+  };
+  let server: Server = match Server::http(addr) {
+    Ok(server) => server,
+    Err(e) => return Err(
+      format!("Error occurred while starting the HTTP server: {}", e)
+    )
+  };
+  let addr: SocketAddr = server.server_addr().to_ip().unwrap_or(addr);
+  let guard: Arc<Guard> = Arc::new(Guard::new(&addr, token.clone()));
+  thread::spawn(move || {
+    for request in server.incoming_requests() {
+      let handle: RemoteHandle = handle.clone();
+      let events: EventBus = events.clone();
+      let guard: Arc<Guard> = guard.clone();
+      // Event streams stay open, so every request gets its own thread.
+      thread::spawn(move || handle_request(request, &handle, &events, &guard));
+    }
+  });
+  return Ok((addr, token));
+}
+
+// Answers a request, or starts streaming events if that's what it asks for.
+fn handle_request(
+  mut request: Request, handle: &RemoteHandle, events: &EventBus,
+  guard: &Guard
+) -> () {
+  if let Err(e) = guard.check(&request) {
+    warn!("Refused an HTTP request for {}: {}", request.url(), e);
+    let _ = request.respond(json_response(403, json!({ "error": e })));
+    return;
+  }
+  let path: Vec<String> = request.url()
+    .split('?')
+    .next()
+    .unwrap_or("")
+    .split('/')
+    .filter(|part| !part.is_empty())
+    .map(|part| part.to_string())
+    .collect();
+  let path: Vec<&str> = path.iter().map(|part| part.as_str()).collect();
+  if *request.method() == Method::Get && path == ["events"] {
+    stream_events(request, events);
+    return;
+  } else if *request.method() == Method::Get && path == ["metrics"] {
+    let _ = request.respond(scrape(handle));
+    return;
+  }
+  let mut body: String = String::new();
+  let (code, reply): (u16, Value) =
+    match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+      Ok(_) => route(request.method(), &path, &body, handle),
+      Err(e) => (400, json!({ "error": e.to_string() }))
+    };
+  let _ = request.respond(json_response(code, reply));
+}
+
+// Makes a response out of a status code and a JSON body.
+fn json_response(code: u16, body: Value) -> Response<Cursor<Vec<u8>>> {
+  return Response::from_string(body.to_string())
+    .with_status_code(code)
+    .with_header(
+      Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .unwrap()
+    );
+}
+
+/**
+ * Sends every event published from now on as a server-sent event, until the
+ * client goes away.
+ */
+fn stream_events(request: Request, events: &EventBus) -> () {
+  let receiver = events.subscribe();
+  let mut writer: Box<dyn Write + Send> = request.into_writer();
+  let head: &str = "HTTP/1.1 200 OK\r\n\
+    Content-Type: text/event-stream\r\n\
+    Cache-Control: no-cache\r\n\
+    Connection: close\r\n\r\n";
+  if writer.write_all(head.as_bytes()).and_then(|_| writer.flush()).is_err() {
+    return;
+  }
+  loop {
+    let chunk: String = match receiver.recv_timeout(KEEP_ALIVE) {
+      Ok(event) => format!(
+        "event: {}\ndata: {}\n\n", event.get_name(), event.to_json()
+      ),
+      // Writing anything at all is how a closed stream is noticed.
+      Err(RecvTimeoutError::Timeout) => ": keep-alive\n\n".to_string(),
+      Err(RecvTimeoutError::Disconnected) => break
+    };
+    if writer.write_all(chunk.as_bytes()).and_then(|_| writer.flush()).is_err()
+    {
+      break;
+    }
+  }
+}
+
+/**
+ * Returns the client's metrics in the Prometheus text format, which is the one
+ * response that isn't JSON. If the client can't be asked for them, scrapers
+ * are told it's unavailable rather than that they asked wrong.
+ */
+fn scrape(handle: &RemoteHandle) -> Response<Cursor<Vec<u8>>> {
+  let (code, body, content_type): (u16, String, &str) =
+    match handle.call(RemoteCall::Status) {
+      Ok(RemoteResponse::Status(status)) =>
+        (200, metrics::render(&status), metrics::CONTENT_TYPE),
+      Ok(_) => (500, json!({ "error": "Expected a status." }).to_string(),
+        "application/json"),
+      Err(e) => (503, json!({ "error": e }).to_string(), "application/json")
+    };
+  return Response::from_string(body)
+    .with_status_code(code)
+    .with_header(
+      Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap()
+    );
+}
+
+/**
+ * Returns the status code and body to answer a request with, given its method,
+ * the parts of its path, and its body.
+ */
+fn route(
+  method: &Method, path: &[&str], body: &str, handle: &RemoteHandle
+) -> (u16, Value) {
+  let command: Command = match (method, path) {
+    (Method::Get, ["status"]) => return call(handle, RemoteCall::Status),
+    (Method::Get, ["config"]) => return call(handle, RemoteCall::Config),
+    (Method::Get, ["slots"]) => {
+      return match call(handle, RemoteCall::Status) {
+        (200, status) => (200, status["slots"].clone()),
+        failed => failed
+      }
+    },
+    (Method::Get, ["slots", i]) => return slot(handle, i),
+    (Method::Get, ["stats"]) => {
+      return match call(handle, RemoteCall::Status) {
+        (200, status) => (200, status["stats"].clone()),
+        failed => failed
+      }
+    },
+    (Method::Get, ["devices"]) => Command::Devices,
+    (Method::Post, ["start"]) => Command::Start,
+    (Method::Post, ["stop"]) => Command::Stop,
+    (Method::Post, ["restart"]) => Command::Restart,
+    (Method::Post, ["release_all"]) => Command::ReleaseAll,
+    (Method::Post, ["config", "reload"]) => Command::Reload,
+    (Method::Post, ["commands"]) => match command::parse(&body_value(body)) {
+      Ok(Some(command)) => command,
+      Ok(None) => return bad_request("The command is blank.".to_string()),
+      Err(e) => return bad_request(e.to_string())
+    },
+    (Method::Put, ["config", "server_ip"]) =>
+      Command::SetServerIp(body_value(body)),
+    (Method::Put, ["config", "auto_assign"]) =>
+      match bool::from_str(&body_value(body)) {
+        Ok(auto_assign) => Command::SetAutoAssign(auto_assign),
+        Err(_) => return bad_request(
+          "auto_assign must be either true or false.".to_string()
+        )
+      },
+    (Method::Put, ["config", "preferred_slots", gamepad_id]) => {
+      let value: String = body_value(body);
+      match (usize::from_str(gamepad_id), value.as_str()) {
+        (Ok(gamepad_id), "none") => Command::SetPreferredSlot(gamepad_id, None),
+        (Ok(gamepad_id), i) => match usize::from_str(i) {
+          Ok(i) => Command::SetPreferredSlot(gamepad_id, Some(i)),
+          Err(_) => return bad_request(
+            "The preferred slot must be a slot index like 0, or none."
+            .to_string()
+          )
+        },
+        (Err(_), _) => return not_found()
+      }
+    },
+    (Method::Put, ["slots", i, key]) => match usize::from_str(i) {
+      Ok(i) => Command::SetSlot(i, key.to_string(), body_value(body)),
+      Err(_) => return not_found()
+    },
+    (Method::Post, ["slots", i, "assign"]) => {
+      match (usize::from_str(i), usize::from_str(&body_value(body))) {
+        (Ok(i), Ok(gamepad_id)) => Command::Assign(gamepad_id, i),
+        (Err(_), _) => return not_found(),
+        (_, Err(_)) => return bad_request(
+          "The body must be the id of a gamepad, as listed by devices."
+          .to_string()
+        )
+      }
+    },
+    (Method::Post, ["slots", i, action @ "kick"])
+      | (Method::Post, ["slots", i, action @ "lock"])
+      | (Method::Post, ["slots", i, action @ "unlock"]) => {
+      match usize::from_str(i) {
+        Ok(i) => match *action {
+          "kick" => Command::Kick(i),
+          "lock" => Command::Lock(i),
+          _ => Command::Unlock(i)
+        },
+        Err(_) => return not_found()
+      }
+    },
+    (Method::Post, ["slots", a, "swap", b]) => {
+      match (usize::from_str(a), usize::from_str(b)) {
+        (Ok(a), Ok(b)) => Command::Swap(a, b),
+        _ => return not_found()
+      }
+    },
+    (_, path) if is_route(path) => return (
+      405,
+      json!({ "error": format!("/{} can't be used that way.", path.join("/")) })
+    ),
+    _ => return not_found()
+  };
+  return call(handle, RemoteCall::Run(command));
+}
+
+// Returns whether a path exists, no matter what method it's used with.
+fn is_route(path: &[&str]) -> bool {
+  return matches!(
+    path,
+    ["status"] | ["stats"] | ["metrics"] | ["config"] | ["slots"]
+      | ["slots", _] | ["devices"]
+      | ["start"] | ["stop"] | ["restart"] | ["release_all"] | ["events"]
+      | ["commands"] | ["config", _] | ["config", "preferred_slots", _]
+      | ["slots", _, _] | ["slots", _, "swap", _]
+  );
+}
+
+// Returns the state and settings of a single slot.
+fn slot(handle: &RemoteHandle, i: &str) -> (u16, Value) {
+  let i: usize = match usize::from_str(i) {
+    Ok(i) => i,
+    Err(_) => return not_found()
+  };
+  let status: Value = match call(handle, RemoteCall::Status) {
+    (200, status) => status["slots"][i].clone(),
+    failed => return failed
+  };
+  let settings: Value = match call(handle, RemoteCall::Config) {
+    (200, config) => config["slot"][i].clone(),
+    failed => return failed
+  };
+  if status.is_null() {
+    return not_found();
+  }
+  return (200, json!({ "status": status, "settings": settings }));
+}
+
+// Makes a call and converts its response to a status code and body.
+fn call(handle: &RemoteHandle, call: RemoteCall) -> (u16, Value) {
+  return match handle.call(call) {
+    Ok(RemoteResponse::Message(msg)) => (200, json!({ "message": msg })),
+    Ok(RemoteResponse::Status(status)) => (200, remote::status_json(&status)),
+    Ok(RemoteResponse::Config(config)) => match serde_json::to_value(&config) {
+      Ok(config) => (200, config),
+      Err(e) => (500, json!({ "error": e.to_string() }))
+    },
+    Err(e) => bad_request(e)
+  }
+}
+
+/**
+ * Reads a single value from a request body. The value can either be given as
+ * is, or as JSON, so both 'Player One' and '"Player One"' work.
+ */
+fn body_value(body: &str) -> String {
+  return match serde_json::from_str::<Value>(body.trim()) {
+    Ok(Value::String(value)) => value,
+    _ => body.trim().to_string()
+  }
+}
+
+fn bad_request(e: String) -> (u16, Value) {
+  return (400, json!({ "error": e }));
+}
+
+fn not_found() -> (u16, Value) {
+  return (404, json!({ "error": "There's nothing here." }));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    config::Config,
+    stats::{
+      ClientStats,
+      StatsTracker
+    },
+    status::ClientStatus
+  };
+  use std::net::TcpStream;
+
+  /**
+   * Starts a server that's answered by a stand-in for the controller. Commands
+   * are answered with how they were parsed, so tests can tell they got through.
+   */
+  fn stand_in() -> (SocketAddr, String) {
+    let (handle, receiver) = remote::channel();
+    thread::spawn(move || {
+      for request in receiver.iter() {
+        let response: Result<RemoteResponse, String> =
+          match request.get_call() {
+            RemoteCall::Run(command) =>
+              Ok(RemoteResponse::Message(format!("{:?}", command))),
+            RemoteCall::Config =>
+              Ok(RemoteResponse::Config(Config::default())),
+            RemoteCall::Status => Ok(RemoteResponse::Status(status()))
+          };
+        request.respond(response);
+      }
+    });
+    let addr: SocketAddr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+    return start(addr, handle, EventBus::new()).unwrap();
+  }
+
+  // Returns the status of a stopped client with no slots.
+  fn status() -> ClientStatus {
+    let stats: ClientStats = StatsTracker::new(time::Duration::from_millis(8))
+      .snapshot("gilrs".to_string(), 0, 5, 0, 0, 0);
+    return ClientStatus::new(
+      false, false, "192.168.0.2".to_string(), 5, None, vec!(), stats
+    );
+  }
+
+  /**
+   * Sends a request made of the given request line and headers, along with a
+   * body. Returns the status code and body of the response.
+   */
+  fn send(addr: &SocketAddr, head: &[String], body: &str) -> (u16, String) {
+    let mut stream: TcpStream = TcpStream::connect(addr).unwrap();
+    let request: String = format!(
+      "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      head.join("\r\n"), body.len(), body
+    );
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let code: u16 = response[9..12].parse().unwrap();
+    let body: String = match response.find("\r\n\r\n") {
+      Some(i) => response[i + 4..].to_string(),
+      None => String::new()
+    };
+    return (code, body);
+  }
+
+  fn post(addr: &SocketAddr, headers: &[&str], body: &str) -> (u16, String) {
+    let mut head: Vec<String> = vec!(
+      "POST /commands HTTP/1.1".to_string(),
+      format!("Host: {}", addr)
+    );
+    head.extend(c![header.to_string(), for header in headers]);
+    return send(addr, &head, body);
+  }
+
+  #[test]
+  fn plain_post_refused() {
+    let (addr, _) = stand_in();
+    let (code, body) = post(&addr, &["Content-Type: text/plain"], "start");
+    assert_eq!(code, 403);
+    assert!(body.contains("application/json"));
+    assert_eq!(post(&addr, &[], "start").0, 403);
+  }
+
+  #[test]
+  fn json_post_answered() {
+    let (addr, _) = stand_in();
+    let (code, body) = post(
+      &addr, &["Content-Type: application/json; charset=utf-8"], "\"start\""
+    );
+    assert_eq!(code, 200);
+    assert!(body.contains("Start"));
+  }
+
+  #[test]
+  fn post_with_token_answered() {
+    let (addr, token) = stand_in();
+    let header: String = format!("{}: {}", TOKEN_HEADER, token);
+    let (code, body) = post(&addr, &[&header], "stop");
+    assert_eq!(code, 200);
+    assert!(body.contains("Stop"));
+    let header: String = format!("{}: {}0", TOKEN_HEADER, token);
+    assert_eq!(post(&addr, &[&header], "stop").0, 403);
+  }
+
+  #[test]
+  fn foreign_origin_refused() {
+    let (addr, _) = stand_in();
+    let json: &str = "Content-Type: application/json";
+    let origin: String = format!("Origin: http://{}", addr);
+    assert_eq!(post(&addr, &[json, &origin], "\"start\"").0, 200);
+    let origin: &str = "Origin: http://evil.example";
+    assert_eq!(post(&addr, &[json, origin], "\"start\"").0, 403);
+  }
+
+  #[test]
+  fn foreign_host_refused() {
+    let (addr, _) = stand_in();
+    let get = |host: Option<String>| -> u16 {
+      let mut head: Vec<String> = vec!("GET /config HTTP/1.1".to_string());
+      head.extend(host);
+      return send(&addr, &head, "").0;
+    };
+    assert_eq!(get(Some(format!("Host: {}", addr))), 200);
+    assert_eq!(get(Some(format!("Host: LOCALHOST:{}", addr.port()))), 200);
+    assert_eq!(get(Some(format!("Host: evil.example:{}", addr.port()))), 403);
+    assert_eq!(get(None), 403);
+  }
+
+  fn get(addr: &SocketAddr, path: &str) -> (u16, String) {
+    let head: Vec<String> = vec!(
+      format!("GET {} HTTP/1.1", path),
+      format!("Host: {}", addr)
+    );
+    return send(addr, &head, "");
+  }
+
+  #[test]
+  fn status_served_as_json() {
+    let (addr, _) = stand_in();
+    let (code, body) = get(&addr, "/status");
+    assert_eq!(code, 200);
+    let status: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(status["running"], json!(false));
+    assert_eq!(status["server_ip"], json!("192.168.0.2"));
+  }
+
+  #[test]
+  fn metrics_served_as_text() {
+    let (addr, _) = stand_in();
+    let (code, body) = get(&addr, "/metrics");
+    assert_eq!(code, 200);
+    assert!(body.lines().any(|line| line == "client_rs_running 0"));
+    assert!(body.lines().any(|line| line == "client_rs_packets_sent_total 5"));
+  }
+
+  #[test]
+  fn metrics_unavailable_without_client() {
+    let (handle, receiver) = remote::channel();
+    drop(receiver);
+    let addr: SocketAddr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+    let (addr, _) = start(addr, handle, EventBus::new()).unwrap();
+    assert_eq!(get(&addr, "/metrics").0, 503);
+  }
+
+  #[test]
+  fn unknown_path_not_found() {
+    let (addr, _) = stand_in();
+    assert_eq!(get(&addr, "/nothing").0, 404);
+  }
+
+  #[test]
+  fn tokens_unique() {
+    assert_ne!(make_token().unwrap(), make_token().unwrap());
+    assert_eq!(make_token().unwrap().len(), 32);
+  }
+}
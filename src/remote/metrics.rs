@@ -0,0 +1,213 @@
+use crate::{
+  stats::ClientStats,
+  status::{
+    ClientStatus,
+    SlotStatus
+  }
+};
+
+use std::fmt::Write;
+
+// The content type of the Prometheus text format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/**
+ * Converts a status snapshot to metrics in the Prometheus text format, so the
+ * client can be scraped. These come from the same numbers shown by 'status'
+ * and 'stats'.
+ */
+pub fn render(status: &ClientStatus) -> String {
+  let stats: &ClientStats = status.get_stats();
+  let adapter: String =
+    format!("adapter=\"{}\"", escape(stats.get_adapter()));
+  let mut out: String = String::new();
+  write_metric(
+    &mut out, "running", "gauge",
+    "Whether the client is sending inputs.",
+    &[("".to_string(), bool_value(status.get_running()))]
+  );
+  write_metric(
+    &mut out, "connected_slots", "gauge",
+    "How many slots have a gamepad in them.",
+    &[(
+      "".to_string(),
+      status.get_slots()
+        .iter()
+        .filter(|slot| slot.get_gamepad().is_some())
+        .count() as f64
+    )]
+  );
+  write_metric(
+    &mut out, "slot_connected", "gauge",
+    "Whether a slot has a gamepad in it.",
+    &slot_samples(status, |slot| bool_value(&slot.get_gamepad().is_some()))
+  );
+  write_metric(
+    &mut out, "slot_input_delay_frames", "gauge",
+    "How many frames of input delay a slot has.",
+    &slot_samples(status, |slot| *slot.get_input_delay() as f64)
+  );
+  write_metric(
+    &mut out, "packets_sent_total", "counter",
+    "How many packets were sent since the client was last started.",
+    &[("".to_string(), *stats.get_packets_sent() as f64)]
+  );
+  write_metric(
+    &mut out, "packets_per_second", "gauge",
+    "How many packets were sent over the last second.",
+    &[("".to_string(), *stats.get_packets_per_second() as f64)]
+  );
+  write_metric(
+    &mut out, "send_errors_total", "counter",
+    "How many packets failed to send since the client was last started.",
+    &[("".to_string(), *stats.get_packets_failed() as f64)]
+  );
+  write_metric(
+    &mut out, "send_jitter_milliseconds", "gauge",
+    "How far off sends were from the update interval over the last second.",
+    &[
+      ("stat=\"mean\"".to_string(), *stats.get_jitter_mean()),
+      ("stat=\"max\"".to_string(), *stats.get_jitter_max())
+    ]
+  );
+  write_metric(
+    &mut out, "adapter_devices", "gauge",
+    "How many gamepads are connected to the input adapter.",
+    &[(adapter.clone(), *stats.get_devices() as f64)]
+  );
+  write_metric(
+    &mut out, "input_events_per_second", "gauge",
+    "How many events were read from the input adapter over the last second.",
+    &[(adapter.clone(), *stats.get_events_per_second() as f64)]
+  );
+  write_metric(
+    &mut out, "input_events_dropped_total", "counter",
+    "How many events the input adapter dropped for having no mapping.",
+    &[(adapter, *stats.get_events_dropped() as f64)]
+  );
+  write_metric(
+    &mut out, "input_queue_depth", "gauge",
+    "How many events are waiting in the input buffer.",
+    &[("".to_string(), *stats.get_queue_depth() as f64)]
+  );
+  return out;
+}
+
+/**
+ * Writes a metric, along with its help text, its type, and a sample for each
+ * set of labels. Every metric's name is prefixed with client_rs.
+ */
+fn write_metric(
+  out: &mut String, name: &str, kind: &str, help: &str,
+  samples: &[(String, f64)]
+) -> () {
+  let _ = writeln!(out, "# HELP client_rs_{} {}", name, help);
+  let _ = writeln!(out, "# TYPE client_rs_{} {}", name, kind);
+  for (labels, value) in samples {
+    if labels.is_empty() {
+      let _ = writeln!(out, "client_rs_{} {}", name, value);
+    } else {
+      let _ = writeln!(out, "client_rs_{}{{{}}} {}", name, labels, value);
+    }
+  }
+}
+
+// Returns a sample for every slot, labelled with the slot's index.
+fn slot_samples<F: Fn(&SlotStatus) -> f64>(
+  status: &ClientStatus, value: F
+) -> Vec<(String, f64)> {
+  return status.get_slots()
+    .iter()
+    .enumerate()
+    .map(|(i, slot)| (format!("slot=\"{}\"", i), value(slot)))
+    .collect();
+}
+
+fn bool_value(b: &bool) -> f64 {
+  return if *b { 1.0 } else { 0.0 };
+}
+
+// Escapes a label value, as the text format requires.
+fn escape(value: &str) -> String {
+  return value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n");
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    input::switch::SwitchPad,
+    stats::StatsTracker
+  };
+  use std::time;
+
+  fn status(adapter: &str) -> ClientStatus {
+    let stats: ClientStats =
+      StatsTracker::new(time::Duration::from_millis(8))
+        .snapshot(adapter.to_string(), 1, 42, 3, 7, 2);
+    let slots: Vec<SlotStatus> = vec!(
+      SlotStatus::new(
+        None, SwitchPad::ProController, 2, Some((0, "Pad".to_string())),
+        false, false, 0, (0, 0), (0, 0)
+      ),
+      SlotStatus::new(
+        None, SwitchPad::Disconnected, 0, None, false, false, 0, (0, 0),
+        (0, 0)
+      )
+    );
+    return ClientStatus::new(
+      true, false, "192.168.0.2".to_string(), 42, None, slots, stats
+    );
+  }
+
+  #[test]
+  fn renders_every_sample() {
+    let out: String = render(&status("gilrs"));
+    let lines: Vec<&str> = out.lines().collect();
+    for expected in &[
+      "client_rs_running 1",
+      "client_rs_connected_slots 1",
+      "client_rs_slot_connected{slot=\"0\"} 1",
+      "client_rs_slot_connected{slot=\"1\"} 0",
+      "client_rs_slot_input_delay_frames{slot=\"0\"} 2",
+      "client_rs_packets_sent_total 42",
+      "client_rs_send_errors_total 3",
+      "client_rs_send_jitter_milliseconds{stat=\"mean\"} 0",
+      "client_rs_adapter_devices{adapter=\"gilrs\"} 1",
+      "client_rs_input_events_dropped_total{adapter=\"gilrs\"} 7",
+      "client_rs_input_queue_depth 2"
+    ] {
+      assert!(lines.contains(expected), "missing '{}' in:\n{}", expected, out);
+    }
+  }
+
+  #[test]
+  fn renders_help_and_type_once() {
+    let out: String = render(&status("gilrs"));
+    let lines: Vec<&str> = out.lines().collect();
+    let i: usize = lines.iter()
+      .position(|line| *line == "# TYPE client_rs_packets_sent_total counter")
+      .unwrap();
+    assert!(lines[i - 1].starts_with("# HELP client_rs_packets_sent_total "));
+    assert_eq!(lines[i + 1], "client_rs_packets_sent_total 42");
+    // Every line is either a comment or a sample of a client_rs metric.
+    assert!(
+      lines.iter().all(|line| {
+        line.starts_with("# HELP client_rs_")
+          || line.starts_with("# TYPE client_rs_")
+          || line.starts_with("client_rs_")
+      })
+    );
+  }
+
+  #[test]
+  fn escapes_label_values() {
+    let out: String = render(&status("a \"b\" \\c\nd"));
+    let expected: &str =
+      "client_rs_adapter_devices{adapter=\"a \\\"b\\\" \\\\c\\nd\"} 1";
+    assert!(out.contains(expected));
+  }
+}
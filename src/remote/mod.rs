@@ -0,0 +1,167 @@
+pub mod event;
+#[cfg(feature = "remote")]
+pub mod http;
+pub mod metrics;
+#[cfg(feature = "remote")]
+pub mod rpc;
+
+use crate::{
+  command::Command,
+  config::Config,
+  stats::ClientStats,
+  status::ClientStatus
+};
+
+use crossbeam_channel::{
+  bounded,
+  unbounded,
+  Receiver,
+  Sender
+};
+use serde_json::{
+  json,
+  Value
+};
+use std::net::SocketAddr;
+
+/**
+ * Represents what another program can ask of the client.
+ * - Run runs a command, exactly as if it were typed into the view.
+ * - Status returns a snapshot of the client's state.
+ * - Config returns the config the client is currently using.
+ */
+#[derive(Clone, Debug)]
+pub enum RemoteCall {
+  Run(Command),
+  Status,
+  Config
+}
+
+// Represents what the controller gives back for a remote call.
+pub enum RemoteResponse {
+  Message(String),
+  Status(ClientStatus),
+  Config(Config)
+}
+
+/**
+ * Represents a call that's waiting on the controller, along with where to send
+ * its response.
+ */
+pub struct RemoteRequest {
+  call: RemoteCall,
+  reply: Sender<Result<RemoteResponse, String>>
+}
+
+impl RemoteRequest {
+  pub fn get_call(&self) -> &RemoteCall {
+    return &self.call;
+  }
+
+  /**
+   * Sends the response back to whoever made the call. If they stopped waiting
+   * for it, the response is simply dropped.
+   */
+  pub fn respond(self, response: Result<RemoteResponse, String>) -> () {
+    let _ = self.reply.send(response);
+  }
+}
+
+/**
+ * Represents a way to make calls to the controller from another thread. Every
+ * server gets its own clone of the same handle.
+ */
+#[derive(Clone)]
+pub struct RemoteHandle {
+  sender: Sender<RemoteRequest>
+}
+
+impl RemoteHandle {
+  /**
+   * Makes a call and waits for the controller to respond to it, which happens
+   * on its next update.
+   */
+  pub fn call(&self, call: RemoteCall) -> Result<RemoteResponse, String> {
+    let (reply, response) = bounded(1);
+    let request: RemoteRequest = RemoteRequest {
+      call: call,
+      reply: reply
+    };
+    if self.sender.send(request).is_err() {
+      return Err("The client isn't accepting calls anymore.".to_string());
+    }
+    return match response.recv() {
+      Ok(response) => response,
+      Err(_) => Err("The client never responded to the call.".to_string())
+    }
+  }
+}
+
+/**
+ * Returns a handle for servers to make calls with, along with the receiver the
+ * controller takes them from.
+ */
+pub fn channel() -> (RemoteHandle, Receiver<RemoteRequest>) {
+  let (sender, receiver) = unbounded();
+  return (RemoteHandle { sender: sender }, receiver);
+}
+
+/**
+ * Makes sure a server is only listening on this machine, since anyone who can
+ * reach it can control the client.
+ */
+pub fn check_local(addr: &SocketAddr, server: &str) -> Result<(), String> {
+  if addr.ip().is_loopback() {
+    return Ok(());
+  } else {
+    return Err(
+      format!(
+        "The {} server can only listen on localhost, like 127.0.0.1, so it \
+        can't listen on {}.",
+        server, addr
+      )
+    );
+  }
+}
+
+// Converts a status snapshot to JSON, so it can be read by other programs.
+pub fn status_json(status: &ClientStatus) -> Value {
+  let slots: Vec<Value> = status.get_slots().iter().map(|slot| {
+    json!({
+      "nickname": slot.get_nickname(),
+      "switch_pad": format!("{:?}", slot.get_switch_pad()),
+      "input_delay": slot.get_input_delay(),
+      "gamepad": slot.get_gamepad().as_ref().map(|(id, name)| json!({
+        "id": id,
+        "name": name
+      })),
+      "held": slot.get_held(),
+      "locked": slot.get_locked(),
+      "buttons": slot.get_buttons(),
+      "left": [slot.get_left().0, slot.get_left().1],
+      "right": [slot.get_right().0, slot.get_right().1]
+    })
+  }).collect();
+  let stats: &ClientStats = status.get_stats();
+  return json!({
+    "running": status.get_running(),
+    "reconnecting": status.get_reconnecting(),
+    "server_ip": status.get_server_ip(),
+    "packets_sent": status.get_packets_sent(),
+    "cleanup_progress": status.get_cleanup(),
+    "slots": slots,
+    "stats": {
+      "adapter": stats.get_adapter(),
+      "devices": stats.get_devices(),
+      "packets_sent": stats.get_packets_sent(),
+      "packets_per_second": stats.get_packets_per_second(),
+      "packets_failed": stats.get_packets_failed(),
+      "jitter_mean_ms": stats.get_jitter_mean(),
+      "jitter_max_ms": stats.get_jitter_max(),
+      "events_per_second": stats.get_events_per_second(),
+      "events_dropped": stats.get_events_dropped(),
+      "queue_depth": stats.get_queue_depth(),
+      "queue_max": stats.get_queue_max()
+    }
+  });
+}
@@ -0,0 +1,601 @@
+use crate::{
+  command::{
+    self,
+    Command
+  },
+  remote::{
+    self,
+    RemoteCall,
+    RemoteHandle,
+    RemoteResponse
+  }
+};
+
+use serde::{
+  de::DeserializeOwned,
+  Deserialize
+};
+use serde_json::{
+  json,
+  Value
+};
+use std::{
+  io::{
+    BufRead,
+    BufReader,
+    Read,
+    Write
+  },
+  net::{
+    SocketAddr,
+    TcpListener
+  },
+  thread
+};
+#[cfg(unix)]
+use std::{
+  fs,
+  os::unix::{
+    fs::{
+      DirBuilderExt,
+      PermissionsExt
+    },
+    net::{
+      UnixListener,
+      UnixStream
+    }
+  },
+  path::{
+    Path,
+    PathBuf
+  },
+  process
+};
+
+// The error codes defined by JSON-RPC 2.0.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// The error code for calls that were understood, but failed to run.
+const CALL_FAILED: i64 = -32000;
+
+// Represents an error that's sent back in place of a result.
+struct RpcError {
+  code: i64,
+  message: String
+}
+
+impl RpcError {
+  fn new(code: i64, message: String) -> RpcError {
+    return RpcError {
+      code: code,
+      message: message
+    }
+  }
+}
+
+/**
+ * The parameters each method can take. These can be given by name or by
+ * position, in the order they're declared in.
+ */
+#[derive(Deserialize)]
+struct ExecuteParams {
+  command: String
+}
+
+#[derive(Deserialize)]
+struct SlotParams {
+  slot: usize
+}
+
+#[derive(Deserialize)]
+struct AssignParams {
+  gamepad_id: usize,
+  slot: usize
+}
+
+#[derive(Deserialize)]
+struct SwapParams {
+  a: usize,
+  b: usize
+}
+
+#[derive(Deserialize)]
+struct SetSlotParams {
+  slot: usize,
+  key: String,
+  value: String
+}
+
+#[derive(Deserialize)]
+struct ServerIpParams {
+  server_ip: String
+}
+
+#[derive(Deserialize)]
+struct AutoAssignParams {
+  auto_assign: bool
+}
+
+#[derive(Deserialize)]
+struct PreferredSlotParams {
+  gamepad_id: usize,
+  slot: Option<usize>
+}
+
+/**
+ * Starts a JSON-RPC server in the background, which makes calls through the
+ * given handle. If the address is an IP and port, the server listens over TCP,
+ * but only on localhost. Otherwise, the address is taken to be the path of a
+ * Unix socket.
+ *
+ * Requests and responses are sent one per line. Since a web page can make a
+ * browser send an HTTP request to any port on localhost, connections that
+ * start like one are dropped, as are connections that send anything that
+ * isn't JSON.
+ */
+pub fn serve(address: &str, handle: RemoteHandle) -> Result<String, String> {
+  return match address.parse::<SocketAddr>() {
+    Ok(addr) => serve_tcp(addr, handle),
+    Err(_) => serve_unix(address, handle)
+  }
+}
+
+fn serve_tcp(addr: SocketAddr, handle: RemoteHandle) -> Result<String, String> {
+  if let Err(e) = remote::check_local(&addr, "JSON-RPC") {
+    return Err(e);
+  }
+  let listener: TcpListener = match TcpListener::bind(addr) {
+    Ok(listener) => listener,
+    Err(e) => return Err(
+      format!("Error occurred while starting the JSON-RPC server: {}", e)
+    )
+  };
+  thread::spawn(move || {
+    for stream in listener.incoming().flatten() {
+      if let Ok(reader) = stream.try_clone() {
+        let handle: RemoteHandle = handle.clone();
+        thread::spawn(move || handle_connection(reader, stream, &handle));
+      }
+    }
+  });
+  return Ok(format!("Listening for JSON-RPC calls on {}.", addr));
+}
+
+#[cfg(unix)]
+fn serve_unix(path: &str, handle: RemoteHandle) -> Result<String, String> {
+  // A socket left behind by an earlier run would keep this one from binding.
+  if Path::new(path).exists() && UnixStream::connect(path).is_err() {
+    let _ = fs::remove_file(path);
+  }
+  let listener: UnixListener = match bind_private(Path::new(path)) {
+    Ok(listener) => listener,
+    Err(e) => return Err(
+      format!("Error occurred while starting the JSON-RPC server: {}", e)
+    )
+  };
+  thread::spawn(move || {
+    for stream in listener.incoming().flatten() {
+      if let Ok(reader) = stream.try_clone() {
+        let handle: RemoteHandle = handle.clone();
+        thread::spawn(move || handle_connection(reader, stream, &handle));
+      }
+    }
+  });
+  return Ok(format!("Listening for JSON-RPC calls on {}.", path));
+}
+
+/**
+ * Binds a Unix socket that only the current user can connect to. The socket is
+ * made in a folder only the current user can enter, and is only moved to the
+ * given path once its own permissions are set, so nobody else can connect to
+ * it in between.
+ */
+#[cfg(unix)]
+fn bind_private(path: &Path) -> Result<UnixListener, String> {
+  let parent: &Path = match path.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent,
+    _ => Path::new(".")
+  };
+  let dir: PathBuf = parent.join(format!(".client-rs-{}", process::id()));
+  if let Err(e) = fs::DirBuilder::new().mode(0o700).create(&dir) {
+    return Err(e.to_string());
+  }
+  let temp: PathBuf = dir.join("socket");
+  let bound: Result<UnixListener, String> = UnixListener::bind(&temp)
+    .and_then(|listener| {
+      fs::set_permissions(&temp, fs::Permissions::from_mode(0o600))?;
+      fs::rename(&temp, path)?;
+      return Ok(listener);
+    })
+    .map_err(|e| e.to_string());
+  let _ = fs::remove_file(&temp);
+  let _ = fs::remove_dir(&dir);
+  return bound;
+}
+
+#[cfg(not(unix))]
+fn serve_unix(path: &str, _handle: RemoteHandle) -> Result<String, String> {
+  return Err(
+    format!(
+      "'{}' isn't an IP and port. Unix sockets aren't supported on this \
+      platform, so use a localhost address like 127.0.0.1:8001 instead.",
+      path
+    )
+  );
+}
+
+/**
+ * Answers every request on a connection until it's closed. The connection is
+ * closed early if it turns out to be an HTTP request, or once a line can't be
+ * parsed, so nothing after it is run.
+ */
+fn handle_connection<R: Read, W: Write>(
+  reader: R, mut writer: W, handle: &RemoteHandle
+) -> () {
+  let mut first: bool = true;
+  for line in BufReader::new(reader).lines() {
+    let line: String = match line {
+      Ok(line) => line,
+      Err(_) => break
+    };
+    if line.trim().is_empty() {
+      continue;
+    }
+    if first && is_http(&line) {
+      warn!("Refused a JSON-RPC connection that sent an HTTP request.");
+      break;
+    }
+    first = false;
+    let (response, keep_open) = match handle_line(&line, handle) {
+      Ok(response) => (response, true),
+      Err(response) => (Some(response), false)
+    };
+    if let Some(response) = response {
+      if writeln!(writer, "{}", response).is_err() {
+        break;
+      }
+    }
+    if !keep_open {
+      break;
+    }
+  }
+}
+
+// Returns whether a line looks like the start of an HTTP request.
+fn is_http(line: &str) -> bool {
+  let parts: Vec<&str> = line.split_whitespace().collect();
+  return parts.len() == 3 && parts[2].starts_with("HTTP/");
+}
+
+/**
+ * Answers a single request. Requests without an id are notifications, which
+ * are still run, but never get a response. If the line can't be parsed at all,
+ * the error to respond with is returned instead.
+ */
+fn handle_line(
+  line: &str, handle: &RemoteHandle
+) -> Result<Option<Value>, Value> {
+  let request: Value = match serde_json::from_str(line) {
+    Ok(request) => request,
+    Err(e) => return Err(
+      error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string()))
+    )
+  };
+  if !request.is_object() {
+    return Ok(
+      Some(
+        error_response(
+          Value::Null,
+          RpcError::new(
+            INVALID_REQUEST, "A request has to be an object.".to_string()
+          )
+        )
+      )
+    );
+  }
+  let result: Result<Value, RpcError> = handle_request(&request, handle);
+  return Ok(
+    request.get("id").cloned().map(|id| {
+      match result {
+        Ok(result) => json!({
+          "jsonrpc": "2.0",
+          "id": id,
+          "result": result
+        }),
+        Err(e) => error_response(id, e)
+      }
+    })
+  );
+}
+
+fn error_response(id: Value, e: RpcError) -> Value {
+  return json!({
+    "jsonrpc": "2.0",
+    "id": id,
+    "error": {
+      "code": e.code,
+      "message": e.message
+    }
+  });
+}
+
+// Makes the call a request asks for and returns its result.
+fn handle_request(
+  request: &Value, handle: &RemoteHandle
+) -> Result<Value, RpcError> {
+  let method: &str = match request.get("method").and_then(Value::as_str) {
+    Some(method) => method,
+    None => return Err(
+      RpcError::new(
+        INVALID_REQUEST, "A request has to have a method.".to_string()
+      )
+    )
+  };
+  let params: Value = request.get("params").cloned().unwrap_or(json!({}));
+  let call: RemoteCall = match to_call(method, params) {
+    Ok(call) => call,
+    Err(e) => return Err(e)
+  };
+  return match handle.call(call) {
+    Ok(RemoteResponse::Message(msg)) => Ok(Value::String(msg)),
+    Ok(RemoteResponse::Status(status)) => Ok(remote::status_json(&status)),
+    Ok(RemoteResponse::Config(config)) => serde_json::to_value(&config)
+      .map_err(|e| RpcError::new(CALL_FAILED, e.to_string())),
+    Err(e) => Err(RpcError::new(CALL_FAILED, e))
+  }
+}
+
+// Reads the parameters of a method.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+  return serde_json::from_value(params)
+    .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()));
+}
+
+// Converts a method and its parameters to a call.
+fn to_call(method: &str, params: Value) -> Result<RemoteCall, RpcError> {
+  let command: Result<Command, RpcError> = match method {
+    "status" => return Ok(RemoteCall::Status),
+    "config" => return Ok(RemoteCall::Config),
+    "execute" => parse_params::<ExecuteParams>(params).and_then(|params| {
+      match command::parse(&params.command) {
+        Ok(Some(command)) => Ok(command),
+        Ok(None) => Err(
+          RpcError::new(INVALID_PARAMS, "The command is blank.".to_string())
+        ),
+        Err(e) => Err(RpcError::new(INVALID_PARAMS, e.to_string()))
+      }
+    }),
+    "start" => Ok(Command::Start),
+    "stop" => Ok(Command::Stop),
+    "restart" => Ok(Command::Restart),
+    "release_all" => Ok(Command::ReleaseAll),
+    "reload" => Ok(Command::Reload),
+    "devices" => Ok(Command::Devices),
+    "assign" => parse_params::<AssignParams>(params)
+      .map(|params| Command::Assign(params.gamepad_id, params.slot)),
+    "kick" => parse_params::<SlotParams>(params)
+      .map(|params| Command::Kick(params.slot)),
+    "swap" => parse_params::<SwapParams>(params)
+      .map(|params| Command::Swap(params.a, params.b)),
+    "lock" => parse_params::<SlotParams>(params)
+      .map(|params| Command::Lock(params.slot)),
+    "unlock" => parse_params::<SlotParams>(params)
+      .map(|params| Command::Unlock(params.slot)),
+    "get_slot" => parse_params::<SlotParams>(params)
+      .map(|params| Command::ShowSlot(params.slot)),
+    "set_slot" => parse_params::<SetSlotParams>(params)
+      .map(|params| Command::SetSlot(params.slot, params.key, params.value)),
+    "set_server_ip" => parse_params::<ServerIpParams>(params)
+      .map(|params| Command::SetServerIp(params.server_ip)),
+    "set_auto_assign" => parse_params::<AutoAssignParams>(params)
+      .map(|params| Command::SetAutoAssign(params.auto_assign)),
+    "set_preferred_slot" => parse_params::<PreferredSlotParams>(params)
+      .map(|params| Command::SetPreferredSlot(params.gamepad_id, params.slot)),
+    _ => Err(
+      RpcError::new(
+        METHOD_NOT_FOUND, format!("'{}' is not a valid method.", method)
+      )
+    )
+  };
+  return command.map(RemoteCall::Run);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crossbeam_channel::{
+    unbounded,
+    Receiver
+  };
+  use std::io::Cursor;
+
+  /**
+   * Starts a stand-in for the controller. Commands are answered with how they
+   * were parsed, and are also sent to the returned receiver, so tests can tell
+   * what was run.
+   */
+  fn stand_in() -> (RemoteHandle, Receiver<Command>) {
+    let (handle, receiver) = remote::channel();
+    let (ran, runs) = unbounded();
+    thread::spawn(move || {
+      for request in receiver.iter() {
+        let response: Result<RemoteResponse, String> =
+          match request.get_call().clone() {
+            RemoteCall::Run(Command::Restart) =>
+              Err("The client isn't running.".to_string()),
+            RemoteCall::Run(command) => {
+              let msg: String = format!("{:?}", command);
+              let _ = ran.send(command);
+              Ok(RemoteResponse::Message(msg))
+            },
+            _ => Err("There's nothing here.".to_string())
+          };
+        request.respond(response);
+      }
+    });
+    return (handle, runs);
+  }
+
+  // Sends the given lines over a connection, then returns every response.
+  fn converse(handle: &RemoteHandle, lines: &[&str]) -> Vec<Value> {
+    let input: String = c![format!("{}\n", line), for line in lines].concat();
+    let mut output: Vec<u8> = vec!();
+    handle_connection(Cursor::new(input), &mut output, handle);
+    return String::from_utf8(output).unwrap()
+      .lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+  }
+
+  #[test]
+  fn one_response_per_line() {
+    let (handle, runs) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle,
+      &[
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "start"}"#,
+        "",
+        r#"{"jsonrpc": "2.0", "id": "two", "method": "kick", "params": [2]}"#
+      ]
+    );
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], json!(1));
+    assert_eq!(responses[0]["result"], json!("Start"));
+    assert_eq!(responses[1]["id"], json!("two"));
+    assert_eq!(responses[1]["result"], json!("Kick(2)"));
+    assert_eq!(runs.try_iter().count(), 2);
+  }
+
+  #[test]
+  fn notification_unanswered() {
+    let (handle, runs) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle, &[r#"{"jsonrpc": "2.0", "method": "stop"}"#]
+    );
+    assert!(responses.is_empty());
+    assert_eq!(runs.try_iter().collect::<Vec<Command>>(), vec!(Command::Stop));
+  }
+
+  #[test]
+  fn bad_json_disconnects() {
+    let (handle, runs) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle,
+      &[
+        "not json",
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "stop"}"#
+      ]
+    );
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["error"]["code"], json!(PARSE_ERROR));
+    assert_eq!(responses[0]["id"], Value::Null);
+    assert_eq!(runs.try_iter().count(), 0);
+  }
+
+  #[test]
+  fn http_request_disconnects() {
+    let (handle, runs) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle,
+      &[
+        "POST / HTTP/1.1",
+        "Host: 127.0.0.1:8001",
+        "Content-Type: text/plain",
+        "",
+        r#"{"method": "execute", "params": ["stop"]}"#
+      ]
+    );
+    assert!(responses.is_empty());
+    assert_eq!(runs.try_iter().count(), 0);
+  }
+
+  #[test]
+  fn non_object_refused() {
+    let (handle, _) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle,
+      &["[1, 2]", r#"{"jsonrpc": "2.0", "id": 1, "method": "start"}"#]
+    );
+    // This is still JSON, so the connection is kept open.
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["error"]["code"], json!(INVALID_REQUEST));
+    assert_eq!(responses[1]["result"], json!("Start"));
+  }
+
+  #[test]
+  fn unknown_method_refused() {
+    let (handle, _) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle, &[r#"{"jsonrpc": "2.0", "id": 1, "method": "dance"}"#]
+    );
+    assert_eq!(responses[0]["error"]["code"], json!(METHOD_NOT_FOUND));
+    assert_eq!(
+      responses[0]["error"]["message"], json!("'dance' is not a valid method.")
+    );
+  }
+
+  #[test]
+  fn bad_params_refused() {
+    let (handle, runs) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle,
+      &[
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "kick"}"#,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "swap", "params": [0, "b"]}"#,
+        r#"{"id": 3, "method": "execute", "params": {"command": " "}}"#,
+        r#"{"id": 4, "method": "assign", "params": [0, 1]}"#
+      ]
+    );
+    assert_eq!(responses.len(), 4);
+    for response in &responses[..3] {
+      assert_eq!(response["error"]["code"], json!(INVALID_PARAMS));
+    }
+    assert_eq!(responses[3]["result"], json!("Assign(0, 1)"));
+    assert_eq!(runs.try_iter().count(), 1);
+  }
+
+  #[test]
+  fn failed_call_reported() {
+    let (handle, _) = stand_in();
+    let responses: Vec<Value> = converse(
+      &handle, &[r#"{"jsonrpc": "2.0", "id": 1, "method": "restart"}"#]
+    );
+    assert_eq!(responses[0]["error"]["code"], json!(CALL_FAILED));
+    assert_eq!(
+      responses[0]["error"]["message"], json!("The client isn't running.")
+    );
+  }
+
+  #[test]
+  fn http_request_lines_recognized() {
+    assert!(is_http("GET /status HTTP/1.1"));
+    assert!(is_http("OPTIONS * HTTP/1.0"));
+    assert!(!is_http(r#"{"method": "status"}"#));
+    assert!(!is_http("GET /status"));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn unix_socket_private() {
+    let dir: PathBuf = std::env::temp_dir()
+      .join(format!("client-rs-rpc-{}", process::id()));
+    let _ = fs::create_dir_all(&dir);
+    let path: PathBuf = dir.join("rpc.sock");
+    let (handle, _) = stand_in();
+    assert!(serve(path.to_str().unwrap(), handle).is_ok());
+    let mode: u32 = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // Only the socket should be left behind.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    let mut stream: UnixStream = UnixStream::connect(&path).unwrap();
+    writeln!(stream, r#"{{"jsonrpc": "2.0", "id": 1, "method": "start"}}"#)
+      .unwrap();
+    let mut line: String = String::new();
+    BufReader::new(stream).read_line(&mut line).unwrap();
+    assert!(line.contains("\"result\":\"Start\""));
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,351 @@
+use std::{
+  fmt,
+  time
+};
+
+// How long each window of samples lasts.
+const WINDOW: time::Duration = time::Duration::from_secs(1);
+
+/**
+ * Represents samples taken over a second at a time. Samples are added to the
+ * current second, but are only read once it's over, so what's read is always
+ * from the last full second and doesn't jump around while it's being read.
+ */
+#[derive(Clone)]
+pub struct Window {
+  start: time::Instant,
+  count: u64,
+  sum: f64,
+  max: f64,
+  last: (u64, f64, f64)
+}
+
+impl Window {
+  pub fn new() -> Window {
+    return Window {
+      start: time::Instant::now(),
+      count: 0,
+      sum: 0.0,
+      max: 0.0,
+      last: (0, 0.0, 0.0)
+    }
+  }
+
+  // Adds a sample, moving on to the next second first if it's time to.
+  pub fn add(&mut self, sample: f64) -> () {
+    let elapsed: time::Duration = self.start.elapsed();
+    if elapsed >= WINDOW {
+      self.last = self.finished(elapsed);
+      self.start = time::Instant::now();
+      self.count = 0;
+      self.sum = 0.0;
+      self.max = 0.0;
+    }
+    self.count += 1;
+    self.sum += sample;
+    self.max = self.max.max(sample);
+  }
+
+  /**
+   * Returns the count, sum and max of the last full second. Nothing may have
+   * been added for a while, in which case the last full second was empty.
+   */
+  fn finished(&self, elapsed: time::Duration) -> (u64, f64, f64) {
+    if elapsed < WINDOW {
+      return self.last;
+    } else if elapsed < WINDOW * 2 {
+      return (self.count, self.sum, self.max);
+    } else {
+      return (0, 0.0, 0.0);
+    }
+  }
+
+  // Returns the sum of the samples taken over the last full second.
+  pub fn sum(&self) -> f64 {
+    return self.finished(self.start.elapsed()).1;
+  }
+
+  // Returns the average of the samples taken over the last full second.
+  pub fn mean(&self) -> f64 {
+    let (count, sum, _) = self.finished(self.start.elapsed());
+    if count == 0 {
+      return 0.0;
+    } else {
+      return sum / count as f64;
+    }
+  }
+
+  // Returns the biggest sample taken over the last full second.
+  pub fn max(&self) -> f64 {
+    return self.finished(self.start.elapsed()).2;
+  }
+}
+
+/**
+ * Represents what a controller keeps track of to tell how well the client is
+ * keeping up.
+ * - sends represents every packet that was sent.
+ * - send_jitter represents how far off each send was from the update interval,
+ *   in milliseconds. The time of the last send is kept in last_sent.
+ * - input_events represents how many events were read on each update.
+ * - queue_depth represents how many events were waiting in the input buffer on
+ *   each update.
+ */
+pub struct StatsTracker {
+  interval: time::Duration,
+  last_sent: Option<time::Instant>,
+  sends: Window,
+  send_jitter: Window,
+  input_events: Window,
+  queue_depth: Window
+}
+
+impl StatsTracker {
+  // Constructs a tracker for a controller updated at the given interval.
+  pub fn new(interval: time::Duration) -> StatsTracker {
+    return StatsTracker {
+      interval: interval,
+      last_sent: None,
+      sends: Window::new(),
+      send_jitter: Window::new(),
+      input_events: Window::new(),
+      queue_depth: Window::new()
+    }
+  }
+
+  // Forgets everything tracked so far, like when the client is started.
+  pub fn reset(&mut self) -> () {
+    *self = StatsTracker::new(self.interval);
+  }
+
+  // Records that a packet was just sent.
+  pub fn record_send(&mut self) -> () {
+    let now: time::Instant = time::Instant::now();
+    self.sends.add(1.0);
+    if let Some(last_sent) = self.last_sent {
+      let since: f64 = (now - last_sent).as_secs_f64();
+      let jitter: f64 = (since - self.interval.as_secs_f64()).abs();
+      self.send_jitter.add(jitter * 1000.0);
+    }
+    self.last_sent = Some(now);
+  }
+
+  // Records how many events were read on an update.
+  pub fn record_events(&mut self, events: usize) -> () {
+    self.input_events.add(events as f64);
+  }
+
+  // Records how many events are waiting in the input buffer on an update.
+  pub fn record_queue(&mut self, depth: usize) -> () {
+    self.queue_depth.add(depth as f64);
+  }
+
+  /**
+   * Returns a snapshot of what's been tracked, along with everything else the
+   * tracker doesn't keep itself.
+   */
+  pub fn snapshot(
+    &self, adapter: String, devices: usize, packets_sent: u64,
+    packets_failed: u64, events_dropped: u64, queue_depth: usize
+  ) -> ClientStats {
+    return ClientStats {
+      adapter: adapter,
+      devices: devices,
+      packets_sent: packets_sent,
+      packets_per_second: self.sends.sum() as u64,
+      packets_failed: packets_failed,
+      jitter_mean: self.send_jitter.mean(),
+      jitter_max: self.send_jitter.max(),
+      events_per_second: self.input_events.sum() as u64,
+      events_dropped: events_dropped,
+      queue_depth: queue_depth,
+      queue_max: self.queue_depth.max() as usize
+    }
+  }
+}
+
+/**
+ * Represents a snapshot of how well a client is keeping up, meant to be shown
+ * to users. Averages and maximums are taken over the last full second.
+ * - adapter represents the name of the input adapter being read from, and
+ *   devices represents how many gamepads are connected to it.
+ * - packets_sent and packets_failed represent how many packets were sent, or
+ *   failed to send, since the client was last started.
+ * - packets_per_second represents how many packets were sent.
+ * - jitter_mean and jitter_max represent how far off sends were from the
+ *   update interval, in milliseconds.
+ * - events_per_second represents how many events were read from the adapter.
+ * - events_dropped represents how many events the adapter dropped for having
+ *   no mapping, since the client was loaded.
+ * - queue_depth and queue_max represent how many events are waiting in the
+ *   input buffer, right now and at most.
+ */
+#[derive(Clone)]
+pub struct ClientStats {
+  adapter: String,
+  devices: usize,
+  packets_sent: u64,
+  packets_per_second: u64,
+  packets_failed: u64,
+  jitter_mean: f64,
+  jitter_max: f64,
+  events_per_second: u64,
+  events_dropped: u64,
+  queue_depth: usize,
+  queue_max: usize
+}
+
+impl ClientStats {
+  pub fn get_adapter(&self) -> &String {
+    return &self.adapter;
+  }
+
+  pub fn get_devices(&self) -> &usize {
+    return &self.devices;
+  }
+
+  pub fn get_packets_sent(&self) -> &u64 {
+    return &self.packets_sent;
+  }
+
+  pub fn get_packets_per_second(&self) -> &u64 {
+    return &self.packets_per_second;
+  }
+
+  pub fn get_packets_failed(&self) -> &u64 {
+    return &self.packets_failed;
+  }
+
+  pub fn get_jitter_mean(&self) -> &f64 {
+    return &self.jitter_mean;
+  }
+
+  pub fn get_jitter_max(&self) -> &f64 {
+    return &self.jitter_max;
+  }
+
+  pub fn get_events_per_second(&self) -> &u64 {
+    return &self.events_per_second;
+  }
+
+  pub fn get_events_dropped(&self) -> &u64 {
+    return &self.events_dropped;
+  }
+
+  pub fn get_queue_depth(&self) -> &usize {
+    return &self.queue_depth;
+  }
+
+  pub fn get_queue_max(&self) -> &usize {
+    return &self.queue_max;
+  }
+}
+
+impl fmt::Display for ClientStats {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Packets: {} sent, {} failed since start, {} sent per second.",
+      self.packets_sent, self.packets_failed, self.packets_per_second
+    )?;
+    write!(
+      f,
+      "\nSend jitter: {:.2} ms on average, {:.2} ms at worst.",
+      self.jitter_mean, self.jitter_max
+    )?;
+    write!(
+      f,
+      "\nInput events: {} per second from {} gamepad(s) on {}, {} dropped as \
+      unmapped.",
+      self.events_per_second, self.devices, self.adapter, self.events_dropped
+    )?;
+    write!(
+      f,
+      "\nInput queue: {} event(s) waiting, {} at most.",
+      self.queue_depth, self.queue_max
+    )?;
+    return Ok(());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Makes a window's current second look like it started a while ago.
+  fn age(window: &mut Window, by: time::Duration) -> () {
+    window.start = time::Instant::now() - by;
+  }
+
+  #[test]
+  fn current_second_not_read_yet() {
+    let mut window: Window = Window::new();
+    window.add(3.0);
+    window.add(5.0);
+    assert_eq!(window.sum(), 0.0);
+    assert_eq!(window.mean(), 0.0);
+    assert_eq!(window.max(), 0.0);
+  }
+
+  #[test]
+  fn last_full_second_read() {
+    let mut window: Window = Window::new();
+    window.add(3.0);
+    window.add(5.0);
+    age(&mut window, WINDOW);
+    assert_eq!(window.sum(), 8.0);
+    assert_eq!(window.mean(), 4.0);
+    assert_eq!(window.max(), 5.0);
+  }
+
+  #[test]
+  fn second_read_until_next_one_over() {
+    let mut window: Window = Window::new();
+    window.add(3.0);
+    window.add(5.0);
+    age(&mut window, WINDOW);
+    window.add(10.0);
+    // The first second is what's read until the second one is over.
+    assert_eq!(window.sum(), 8.0);
+    assert_eq!(window.max(), 5.0);
+    age(&mut window, WINDOW);
+    assert_eq!(window.sum(), 10.0);
+    assert_eq!(window.mean(), 10.0);
+  }
+
+  #[test]
+  fn idle_window_reads_empty() {
+    let mut window: Window = Window::new();
+    window.add(3.0);
+    age(&mut window, WINDOW * 2);
+    assert_eq!(window.sum(), 0.0);
+    assert_eq!(window.mean(), 0.0);
+    assert_eq!(window.max(), 0.0);
+    // Nothing was added in the second before this one either.
+    window.add(1.0);
+    assert_eq!(window.sum(), 0.0);
+  }
+
+  #[test]
+  fn tracker_snapshot_counts_everything() {
+    let mut tracker: StatsTracker =
+      StatsTracker::new(time::Duration::from_millis(8));
+    tracker.record_send();
+    tracker.record_send();
+    tracker.record_events(4);
+    tracker.record_queue(6);
+    age(&mut tracker.sends, WINDOW);
+    age(&mut tracker.input_events, WINDOW);
+    age(&mut tracker.queue_depth, WINDOW);
+    let stats: ClientStats =
+      tracker.snapshot("gilrs".to_string(), 2, 10, 1, 3, 0);
+    assert_eq!(*stats.get_packets_per_second(), 2);
+    assert_eq!(*stats.get_events_per_second(), 4);
+    assert_eq!(*stats.get_queue_max(), 6);
+    assert_eq!(*stats.get_packets_sent(), 10);
+    tracker.reset();
+    let stats: ClientStats =
+      tracker.snapshot("gilrs".to_string(), 2, 0, 0, 0, 0);
+    assert_eq!(*stats.get_packets_per_second(), 0);
+  }
+}
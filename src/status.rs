@@ -0,0 +1,227 @@
+use crate::{
+  input::switch::{
+    SwitchButton,
+    SwitchPad
+  },
+  stats::ClientStats
+};
+
+use std::fmt;
+
+/**
+ * Represents a snapshot of a client's state, meant to be shown to users.
+ * - running represents whether the client is running.
+ * - reconnecting represents whether sending to the server keeps failing, and
+ *   is being retried.
+ * - server_ip represents the IP of the target Switch.
+ * - packets_sent represents how many packets were sent since the client was
+ *   last started.
+ * - cleanup represents how far along cleaning up gamepads is from 0 to 1, if
+ *   the client is doing so.
+ * - slots represent the state of every slot.
+ * - stats represent how well the client is keeping up.
+ */
+#[derive(Clone)]
+pub struct ClientStatus {
+  running: bool,
+  reconnecting: bool,
+  server_ip: String,
+  packets_sent: u64,
+  cleanup: Option<f32>,
+  slots: Vec<SlotStatus>,
+  stats: ClientStats
+}
+
+impl ClientStatus {
+  pub fn new(
+    running: bool, reconnecting: bool, server_ip: String, packets_sent: u64,
+    cleanup: Option<f32>, slots: Vec<SlotStatus>, stats: ClientStats
+  ) -> ClientStatus {
+    return ClientStatus {
+      running: running,
+      reconnecting: reconnecting,
+      server_ip: server_ip,
+      packets_sent: packets_sent,
+      cleanup: cleanup,
+      slots: slots,
+      stats: stats
+    }
+  }
+
+  pub fn get_running(&self) -> &bool {
+    return &self.running;
+  }
+
+  pub fn get_reconnecting(&self) -> &bool {
+    return &self.reconnecting;
+  }
+
+  pub fn get_server_ip(&self) -> &String {
+    return &self.server_ip;
+  }
+
+  pub fn get_packets_sent(&self) -> &u64 {
+    return &self.packets_sent;
+  }
+
+  pub fn get_cleanup(&self) -> &Option<f32> {
+    return &self.cleanup;
+  }
+
+  pub fn get_slots(&self) -> &Vec<SlotStatus> {
+    return &self.slots;
+  }
+
+  pub fn get_stats(&self) -> &ClientStats {
+    return &self.stats;
+  }
+}
+
+impl fmt::Display for ClientStatus {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if self.running {
+      write!(
+        f,
+        "Running, sending to {} ({} packets sent since start).",
+        self.server_ip,
+        self.packets_sent
+      )?;
+      if self.reconnecting {
+        write!(f, " The server can't be reached right now, retrying...")?;
+      }
+      write!(f, "\n{}", self.stats)?;
+    } else if let Some(progress) = self.cleanup {
+      write!(
+        f,
+        "Stopped, cleaning up gamepads ({:.0}% done).",
+        progress * 100.0
+      )?;
+    } else {
+      write!(f, "Stopped.")?;
+    }
+    for (i, slot) in self.slots.iter().enumerate() {
+      write!(f, "\nSlot {}", i + 1)?;
+      if let Some(nickname) = slot.get_nickname() {
+        write!(f, " ({})", nickname)?;
+      }
+      write!(f, ": {}", slot)?;
+    }
+    return Ok(());
+  }
+}
+
+/**
+ * Represents a snapshot of a single slot.
+ * - nickname represents the name shown alongside the slot's number, if any.
+ * - switch_pad represents the Switch controller type the slot is emulating
+ *   right now, which is Disconnected if nothing is assigned to it.
+ * - input_delay represents the slot's input delay in frames.
+ * - gamepad represents the ID and name of the gamepad assigned to the slot.
+ * - held represents whether the slot is being held for a gamepad.
+ * - locked represents whether the slot refuses automatic assignment.
+ * - keyout, left and right represent the slot's current inputs.
+ */
+#[derive(Clone)]
+pub struct SlotStatus {
+  nickname: Option<String>,
+  switch_pad: SwitchPad,
+  input_delay: u8,
+  gamepad: Option<(usize, String)>,
+  held: bool,
+  locked: bool,
+  keyout: i32,
+  left: (i32, i32),
+  right: (i32, i32)
+}
+
+impl SlotStatus {
+  pub fn new(
+    nickname: Option<String>, switch_pad: SwitchPad, input_delay: u8,
+    gamepad: Option<(usize, String)>, held: bool, locked: bool, keyout: i32,
+    left: (i32, i32), right: (i32, i32)
+  ) -> SlotStatus {
+    return SlotStatus {
+      nickname: nickname,
+      switch_pad: switch_pad,
+      input_delay: input_delay,
+      gamepad: gamepad,
+      held: held,
+      locked: locked,
+      keyout: keyout,
+      left: left,
+      right: right
+    }
+  }
+
+  pub fn get_nickname(&self) -> &Option<String> {
+    return &self.nickname;
+  }
+
+  pub fn get_switch_pad(&self) -> &SwitchPad {
+    return &self.switch_pad;
+  }
+
+  pub fn get_input_delay(&self) -> &u8 {
+    return &self.input_delay;
+  }
+
+  pub fn get_gamepad(&self) -> &Option<(usize, String)> {
+    return &self.gamepad;
+  }
+
+  pub fn get_held(&self) -> &bool {
+    return &self.held;
+  }
+
+  pub fn get_locked(&self) -> &bool {
+    return &self.locked;
+  }
+
+  pub fn get_keyout(&self) -> &i32 {
+    return &self.keyout;
+  }
+
+  pub fn get_left(&self) -> &(i32, i32) {
+    return &self.left;
+  }
+
+  pub fn get_right(&self) -> &(i32, i32) {
+    return &self.right;
+  }
+
+  // Returns the names of the Switch buttons pressed in this slot.
+  pub fn get_buttons(&self) -> Vec<String> {
+    return SwitchButton::decode(&self.keyout)
+      .iter()
+      .map(|button| format!("{:?}", button))
+      .collect();
+  }
+}
+
+impl fmt::Display for SlotStatus {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?}, {} frame(s) of delay", self.switch_pad, self.input_delay)?;
+    match &self.gamepad {
+      Some((gamepad_id, name)) => write!(f, ", {} (id: {})", name, gamepad_id)?,
+      None if self.held => write!(f, ", held")?,
+      None => write!(f, ", empty")?
+    }
+    if self.locked {
+      write!(f, ", locked")?;
+    }
+    if self.switch_pad != SwitchPad::Disconnected {
+      let buttons: Vec<String> = self.get_buttons();
+      write!(
+        f,
+        "\n  Buttons: {}",
+        if buttons.is_empty() { "none".to_string() } else { buttons.join(" ") }
+      )?;
+      write!(
+        f,
+        "\n  Left stick: ({}, {}), right stick: ({}, {})",
+        self.left.0, self.left.1, self.right.0, self.right.1
+      )?;
+    }
+    return Ok(());
+  }
+}
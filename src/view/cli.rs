@@ -1,11 +1,38 @@
 use crate::{
+  config::{
+    self,
+    SLOT_KEYS
+  },
+  input::switch::SwitchPad,
+  status::ClientStatus,
   view::common::{
     ClientView
   }
 };
 
+use rustyline::{
+  completion::Completer,
+  error::ReadlineError,
+  highlight::Highlighter,
+  hint::Hinter,
+  validate::Validator,
+  Context,
+  Editor,
+  ExternalPrinter,
+  Helper
+};
 use std::{
-  io::stdin,
+  io::{
+    stdin,
+    stdout,
+    IsTerminal,
+    Stdout,
+    Write
+  },
+  path::{
+    Path,
+    PathBuf
+  },
   sync::mpsc,
   sync::mpsc::{
     Receiver,
@@ -13,29 +40,117 @@ use std::{
   },
   thread
 };
+use strum::IntoEnumIterator;
 
 /**
  * Represents a command line view that reads from stdin and adds inputs to a
  * command buffer.
+ *
+ * If stdin is a terminal, commands are read with line editing, history, and
+ * tab completion. Anything written to this view while a command is being typed
+ * is printed above the prompt through "printer", so the two don't get mixed up.
+ *
+ * If stdin is piped instead, every line is run as a command until it runs out,
+ * after which "receiver" is dropped and the client keeps running without it.
+ * A headless view has no receiver to begin with and never touches stdin.
+ *
+ * Commands are only read from the first update on, which is when "input" is
+ * started. Until then, there's no prompt for anything written (like a startup
+ * script's output) to get mixed up with.
  */
 pub struct CLIView {
-  receiver: Receiver<String>,
+  input: Option<Input>,
+  receiver: Option<Receiver<String>>,
+  printer: Option<Box<dyn ExternalPrinter + Send>>,
   command_buffer: Vec<String>
 }
 
+// Represents how a view reads commands once it starts reading them.
+enum Input {
+  Editor(CommandHelper, PathBuf),
+  Stdin
+}
+
 impl CLIView {
-  // Constructs a view with an stdin channel and an empty command buffer.
-  pub fn new() -> CLIView {
+  /**
+   * Constructs a view that reads from stdin, with an empty command buffer. The
+   * given command names, number of slots, and saved profiles are used for tab
+   * completion, and the history of entered commands is kept next to the config.
+   */
+  pub fn new(
+    commands: &[&str], num_slots: usize, config_path: &Path
+  ) -> CLIView {
+    let input: Input = if stdin().is_terminal() {
+      let history_path: PathBuf = match config_path.parent() {
+        Some(dir) => dir.join("history.txt"),
+        None => PathBuf::from("history.txt")
+      };
+      Input::Editor(
+        CommandHelper::new(commands, num_slots, config_path), history_path
+      )
+    } else {
+      Input::Stdin
+    };
     return CLIView {
-      receiver: spawn_stdin_channel(),
+      input: Some(input),
+      receiver: None,
+      printer: None,
       command_buffer: vec!()
     }
   }
+
+  /**
+   * Constructs a view that only writes output and never reads from stdin, so
+   * the client can be run as a service.
+   */
+  pub fn headless() -> CLIView {
+    return CLIView {
+      input: None,
+      receiver: None,
+      printer: None,
+      command_buffer: vec!()
+    }
+  }
+
+  /**
+   * Starts reading commands, if this view hasn't yet. The line editor falls
+   * back to plain stdin if it can't be used.
+   */
+  fn start(&mut self) -> () {
+    match self.input.take() {
+      Some(Input::Editor(helper, history_path)) => {
+        match spawn_editor_channel(helper, history_path) {
+          Ok((receiver, printer)) => {
+            self.receiver = Some(receiver);
+            self.printer = Some(printer);
+          },
+          Err(_) => self.receiver = Some(spawn_stdin_channel())
+        }
+      },
+      Some(Input::Stdin) => self.receiver = Some(spawn_stdin_channel()),
+      None => ()
+    }
+  }
+
+  /**
+   * Prints a string, going through the printer if there is one. Output that
+   * can't be written is dropped, since the terminal may already be closed
+   * while the client cleans up.
+   */
+  fn print(&mut self, s: String) -> () {
+    if let Some(printer) = &mut self.printer {
+      if printer.print(s.to_string()).is_ok() {
+        return;
+      }
+    }
+    let mut out: Stdout = stdout();
+    let _ = out.write_all(s.as_bytes()).and_then(|_| out.flush());
+  }
 }
 
 /**
  * Spawns a stdin channel so we can read inputs from stdin in a non-blocking
- * way.
+ * way. The channel is disconnected once stdin is closed or can't be read.
  *
  * Credits: https://stackoverflow.com/a/55201400
  */
@@ -43,15 +158,68 @@ fn spawn_stdin_channel() -> Receiver<String> {
   let (tx, rx) = mpsc::channel::<String>();
   thread::spawn(move || loop {
     let mut buffer = String::new();
-    stdin().read_line(&mut buffer).unwrap();
-    tx.send(buffer).unwrap();
+    match stdin().read_line(&mut buffer) {
+      // Reading 0 bytes means we've reached the end of stdin.
+      Ok(0) | Err(_) => break,
+      Ok(_) => if tx.send(buffer).is_err() {
+        break;
+      }
+    }
   });
   return rx;
 }
 
+/**
+ * Like spawn_stdin_channel(), but reads through a line editor. The printer
+ * it returns has to be used for any output while the editor is reading.
+ *
+ * Interrupting or ending input (Ctrl-C or Ctrl-D) is read as 'exit'.
+ */
+fn spawn_editor_channel(
+  helper: CommandHelper, history_path: PathBuf
+) -> Result<(Receiver<String>, Box<dyn ExternalPrinter + Send>), String> {
+  let mut editor: Editor<CommandHelper> = match Editor::new() {
+    Ok(editor) => editor,
+    Err(e) => return Err(e.to_string())
+  };
+  editor.set_helper(Some(helper));
+  let _ = editor.load_history(&history_path);
+  let printer: Box<dyn ExternalPrinter + Send> =
+    match editor.create_external_printer() {
+      Ok(printer) => Box::new(printer),
+      Err(e) => return Err(e.to_string())
+    };
+
+  let (tx, rx) = mpsc::channel::<String>();
+  thread::spawn(move || loop {
+    let command: String = match editor.readline("> ") {
+      Ok(line) => {
+        if !line.trim().is_empty() {
+          editor.add_history_entry(line.as_str());
+          let _ = editor.save_history(&history_path);
+        }
+        line
+      },
+      Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
+        "exit".to_string()
+      },
+      Err(_) => break
+    };
+    if tx.send(command).is_err() {
+      break;
+    }
+  });
+  return Ok((rx, printer));
+}
+
 impl ClientView for CLIView {
   fn update(&mut self) -> Result<(), String> {
-    return match self.receiver.try_recv() {
+    self.start();
+    let received: Result<String, TryRecvError> = match &self.receiver {
+      Some(receiver) => receiver.try_recv(),
+      None => return Ok(())
+    };
+    return match received {
       Ok(command) => {
         self.command_buffer.insert(
           0,
@@ -64,23 +232,242 @@ impl ClientView for CLIView {
         return Ok(());
       },
       Err(TryRecvError::Empty) => Ok(()),
-      Err(TryRecvError::Disconnected) => Err(
-        "stdin channel is disconnected.".to_string()
-      )
+      // Running out of commands isn't an error, there just won't be any more.
+      Err(TryRecvError::Disconnected) => {
+        self.receiver = None;
+        self.writeln(
+          "Stopped reading commands, stdin has been closed.".to_string()
+        );
+        return Ok(());
+      }
     }
   }
 
   fn write(&mut self, s: String) -> () {
-    print!("[CLIENT]: {}", s);
+    self.print(format!("[CLIENT]: {}", s));
   }
 
   fn writeln(&mut self, s: String) -> () {
-    println!("[CLIENT]: {}", s);
+    self.print(format!("[CLIENT]: {}\n", s));
+  }
+
+  /**
+   * Clears the terminal and moves the cursor back to the top left, so the
+   * status is redrawn in the same place every time.
+   */
+  fn watch_status(&mut self, status: &ClientStatus) -> () {
+    self.print("\x1B[2J\x1B[H".to_string());
+    self.writeln(format!("{}\nPress enter to stop watching.", status));
   }
 
+  // The state is only ever printed when asked for with 'status'.
+  fn update_status(&mut self, _status: &ClientStatus) -> () {}
+
+  fn close(&mut self) -> () {}
+
   fn get_command_buffer(&mut self) -> Vec<String> {
     let cloned: Vec<String> = self.command_buffer.clone();
     self.command_buffer.clear();
     return cloned;
   }
 }
+
+/**
+ * Represents a helper for the line editor, which tab completes command names,
+ * slot indices, Switch pad types, and profile names depending on where the
+ * cursor is.
+ */
+struct CommandHelper {
+  commands: Vec<String>,
+  num_slots: usize,
+  config_path: PathBuf
+}
+
+impl CommandHelper {
+  fn new(
+    commands: &[&str], num_slots: usize, config_path: &Path
+  ) -> CommandHelper {
+    return CommandHelper {
+      commands: commands.iter().map(|command| command.to_string()).collect(),
+      num_slots: num_slots,
+      config_path: config_path.to_path_buf()
+    }
+  }
+
+  /**
+   * Returns every possible value for the next argument of a command, given the
+   * arguments before it.
+   */
+  fn candidates(&self, keyword: &str, args: &[&str]) -> Vec<String> {
+    let slots: Vec<String> = c![i.to_string(), for i in 0..self.num_slots];
+    return match (keyword, args.len()) {
+      ("help", 0) => self.commands.clone(),
+      ("slot", 0) | ("kick", 0) | ("lock", 0) | ("unlock", 0) | ("swap", 0)
+        | ("swap", 1) | ("assign", 1) => slots,
+      ("slot", 1) => vec!("set".to_string()),
+      ("slot", 2) => c![key.to_string(), for key in SLOT_KEYS],
+      ("slot", 3) if args[2] == "switch_pad" => SwitchPad::iter()
+        .map(|switch_pad| format!("{:?}", switch_pad))
+        .collect(),
+      ("slot", 3) if args[2] == "nickname" || args[2] == "mapping"
+        || args[2] == "reserved_device" => vec!("none".to_string()),
+      ("set_preferred_slot", 1) => {
+        let mut candidates: Vec<String> = slots;
+        candidates.push("none".to_string());
+        candidates
+      },
+      ("set_auto_assign", 0) => vec!("true".to_string(), "false".to_string()),
+      ("status", 0) => vec!("--watch".to_string()),
+      ("profile", 0) => c![
+        action.to_string(), for action in ["save", "load", "list", "delete"]
+      ],
+      // Profiles are listed every time, since they can be saved at any point.
+      ("profile", 1) => config::list_profiles(&self.config_path)
+        .unwrap_or(vec!()),
+      _ => vec!()
+    }
+  }
+}
+
+impl Completer for CommandHelper {
+  type Candidate = String;
+
+  fn complete(
+    &self, line: &str, pos: usize, _ctx: &Context
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    let before: &str = &line[..pos];
+    let start: usize = before.rfind(' ').map(|j| j + 1).unwrap_or(0);
+    let prefix: &str = &before[start..];
+    let words: Vec<&str> = before[..start].split_whitespace().collect();
+    let candidates: Vec<String> = match words.split_first() {
+      None => self.commands.clone(),
+      Some((keyword, args)) => self.candidates(keyword, args)
+    };
+    let mut matches: Vec<String> = candidates
+      .into_iter()
+      .filter(|candidate| candidate.starts_with(prefix))
+      .collect();
+    // A lone match is finished off with a space so the next word can be typed.
+    if matches.len() == 1 {
+      matches[0].push(' ');
+    }
+    return Ok((start, matches));
+  }
+}
+
+impl Hinter for CommandHelper {
+  type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rustyline::history::History;
+  use std::{
+    env,
+    fs,
+    process
+  };
+
+  // Completes a line as if the cursor were at its end.
+  fn complete(helper: &CommandHelper, line: &str) -> (usize, Vec<String>) {
+    let history: History = History::new();
+    return helper
+      .complete(line, line.len(), &Context::new(&history))
+      .unwrap();
+  }
+
+  fn helper(config_path: &Path) -> CommandHelper {
+    return CommandHelper::new(
+      &["slot", "start", "status", "stop", "kick", "profile"],
+      4,
+      config_path
+    );
+  }
+
+  #[test]
+  fn completes_command_names() {
+    let helper: CommandHelper = helper(Path::new("config.toml"));
+    assert_eq!(
+      complete(&helper, "st"),
+      (0, vec!("start".to_string(), "status".to_string(), "stop".to_string()))
+    );
+    assert_eq!(complete(&helper, "ki"), (0, vec!("kick ".to_string())));
+  }
+
+  #[test]
+  fn completes_slot_indices_and_pad_types() {
+    let helper: CommandHelper = helper(Path::new("config.toml"));
+    assert_eq!(complete(&helper, "kick ").1, vec!("0", "1", "2", "3"));
+    assert_eq!(
+      complete(&helper, "slot 0 set switch_pad JoyConL"),
+      (22, vec!("JoyConLSide ".to_string()))
+    );
+    assert!(complete(&helper, "slot 0 set nope ").1.is_empty());
+  }
+
+  #[test]
+  fn completes_saved_profiles() {
+    let dir: PathBuf = env::temp_dir()
+      .join(format!("client-rs-cli-{}", process::id()));
+    let config_path: PathBuf = dir.join("config.toml");
+    let path: PathBuf = config::profile_path(&config_path, "smash").unwrap();
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "").unwrap();
+    let helper: CommandHelper = helper(&config_path);
+    assert_eq!(
+      complete(&helper, "profile load sm"),
+      (13, vec!("smash ".to_string()))
+    );
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  // Constructs a view that reads lines from a channel, like piped stdin.
+  fn piped(lines: &[&str]) -> CLIView {
+    let (tx, rx) = mpsc::channel::<String>();
+    for line in lines {
+      tx.send(line.to_string()).unwrap();
+    }
+    return CLIView {
+      input: None,
+      receiver: Some(rx),
+      printer: None,
+      command_buffer: vec!()
+    }
+  }
+
+  #[test]
+  fn piped_commands_run_in_order() {
+    let mut view: CLIView = piped(&["start\n", "stop\r\n", "exit"]);
+    let mut commands: Vec<String> = vec!();
+    for _i in 0..3 {
+      view.update().unwrap();
+      commands.extend(view.get_command_buffer());
+    }
+    assert_eq!(commands, vec!("start", "stop", "exit"));
+  }
+
+  #[test]
+  fn piped_view_keeps_running_once_stdin_closes() {
+    let mut view: CLIView = piped(&["start\n"]);
+    view.update().unwrap();
+    view.update().unwrap();
+    assert!(view.receiver.is_none());
+    assert!(view.update().is_ok());
+    assert_eq!(view.get_command_buffer(), vec!("start"));
+  }
+
+  #[test]
+  fn headless_view_never_reads() {
+    let mut view: CLIView = CLIView::headless();
+    assert!(view.update().is_ok());
+    assert!(view.receiver.is_none());
+    assert!(view.get_command_buffer().is_empty());
+  }
+}
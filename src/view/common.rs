@@ -1,3 +1,5 @@
+use crate::status::ClientStatus;
+
 pub trait ClientView {
   /**
    * Updates this view.
@@ -13,6 +15,24 @@ pub trait ClientView {
   // Writes a given string to this view, but with a new line at the end.
   fn writeln(&mut self, s: String) -> ();
 
+  /**
+   * Shows the status while it's being watched, in place of the last one shown.
+   * Views that already show the status as it changes can just ignore this.
+   */
+  fn watch_status(&mut self, status: &ClientStatus) -> ();
+
+  /**
+   * Gives this view a snapshot of the client's current state. Views that don't
+   * show the state as it changes can just ignore this.
+   */
+  fn update_status(&mut self, status: &ClientStatus) -> ();
+
+  /**
+   * Closes this view right before the client exits, giving back anything it
+   * took over, such as the terminal.
+   */
+  fn close(&mut self) -> ();
+
   /**
    * Returns a copy of this view's command buffer.
    *
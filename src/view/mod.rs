@@ -1,2 +1,5 @@
+#[cfg(feature = "cli")]
 pub mod cli;
 pub mod common;
+#[cfg(feature = "tui")]
+pub mod tui;
@@ -0,0 +1,459 @@
+use crate::{
+  input::switch::{
+    SwitchButton,
+    SwitchPad
+  },
+  status::ClientStatus,
+  view::common::ClientView
+};
+
+use crossterm::{
+  event::{
+    self,
+    Event,
+    KeyCode,
+    KeyEvent,
+    KeyEventKind,
+    KeyModifiers
+  },
+  execute,
+  terminal::{
+    self,
+    EnterAlternateScreen,
+    LeaveAlternateScreen
+  }
+};
+use std::{
+  cmp,
+  io::{
+    stdout,
+    Stdout
+  },
+  time
+};
+use tui::{
+  backend::{
+    Backend,
+    CrosstermBackend
+  },
+  layout::{
+    Constraint,
+    Direction,
+    Layout,
+    Rect
+  },
+  style::{
+    Color,
+    Modifier,
+    Style
+  },
+  text::{
+    Span,
+    Spans
+  },
+  widgets::{
+    Block,
+    Borders,
+    Cell,
+    Paragraph,
+    Row,
+    Table
+  },
+  Frame,
+  Terminal
+};
+
+// How many lines the log keeps before it starts dropping the oldest ones.
+const LOG_CAPACITY: usize = 1000;
+
+/**
+ * Represents a full-screen terminal view with a panel of slots, a visualizer
+ * of every slot's inputs, a scrolling log, and a command prompt.
+ *
+ * The terminal is put in raw mode while this view is open, so Ctrl-C is read
+ * as a key rather than a signal. It's treated as typing 'exit'.
+ */
+pub struct TUIView {
+  terminal: Terminal<CrosstermBackend<Stdout>>,
+  log: Vec<String>,
+  // Whether the last line of the log was written without a new line.
+  log_open: bool,
+  // How many lines the log is scrolled up by.
+  scroll: usize,
+  prompt: String,
+  status: Option<ClientStatus>,
+  command_buffer: Vec<String>,
+  dirty: bool
+}
+
+impl TUIView {
+  /**
+   * Constructs a view by switching the terminal to an alternate screen in raw
+   * mode. This fails if stdout isn't a terminal, in which case the terminal is
+   * left as it was.
+   */
+  pub fn new() -> Result<TUIView, String> {
+    if let Err(e) = terminal::enable_raw_mode() {
+      return Err(format!("Couldn't put the terminal in raw mode: {}", e));
+    }
+    let mut out: Stdout = stdout();
+    if let Err(e) = execute!(out, EnterAlternateScreen) {
+      let _ = terminal::disable_raw_mode();
+      return Err(format!("Couldn't open an alternate screen: {}", e));
+    }
+    return match Terminal::new(CrosstermBackend::new(out)) {
+      Ok(terminal) => Ok(
+        TUIView {
+          terminal: terminal,
+          log: vec!(),
+          log_open: false,
+          scroll: 0,
+          prompt: String::new(),
+          status: None,
+          command_buffer: vec!(),
+          dirty: true
+        }
+      ),
+      Err(e) => {
+        let _ = execute!(stdout(), LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+        Err(format!("Couldn't set up the terminal: {}", e))
+      }
+    }
+  }
+
+  // Adds text to the log, splitting it into lines.
+  fn push_log(&mut self, s: String, newline: bool) -> () {
+    for (j, line) in s.split('\n').enumerate() {
+      let line: String = line.trim_end_matches('\r').to_string();
+      if j == 0 && self.log_open {
+        if let Some(last) = self.log.last_mut() {
+          last.push_str(&line);
+          continue;
+        }
+      }
+      self.log.push(line);
+    }
+    self.log_open = !newline;
+    if self.log.len() > LOG_CAPACITY {
+      self.log.drain(0..(self.log.len() - LOG_CAPACITY));
+    }
+    self.dirty = true;
+  }
+
+  // Handles a key press, which either edits the prompt or scrolls the log.
+  fn handle_key(&mut self, key: &KeyEvent) -> () {
+    if key.kind == KeyEventKind::Release {
+      return;
+    }
+    match key.code {
+      KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        self.command_buffer.insert(0, "exit".to_string());
+      },
+      KeyCode::Char(c) => self.prompt.push(c),
+      KeyCode::Backspace => {
+        self.prompt.pop();
+      },
+      KeyCode::Esc => self.prompt.clear(),
+      KeyCode::Enter => {
+        let command: String = self.prompt.drain(..).collect();
+        self.push_log(format!("> {}", command), true);
+        self.command_buffer.insert(0, command);
+        self.scroll = 0;
+      },
+      KeyCode::PageUp => self.scroll = self.scroll.saturating_add(10),
+      KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+      _ => ()
+    }
+    self.dirty = true;
+  }
+
+  // Redraws the whole screen.
+  fn draw(&mut self) -> Result<(), String> {
+    let log: &Vec<String> = &self.log;
+    let scroll: &mut usize = &mut self.scroll;
+    let prompt: &String = &self.prompt;
+    let status: &Option<ClientStatus> = &self.status;
+    let num_slots: u16 = match status {
+      Some(status) => status.get_slots().len() as u16,
+      None => 0
+    };
+    return match self.terminal.draw(|f| {
+      let rows: Vec<Rect> = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+          [
+            Constraint::Length(num_slots + 3),
+            Constraint::Min(6),
+            Constraint::Length(3)
+          ].as_ref()
+        )
+        .split(f.size());
+      let columns: Vec<Rect> = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+          [Constraint::Length(48), Constraint::Min(20)].as_ref()
+        )
+        .split(rows[1]);
+      draw_slots(f, rows[0], status);
+      draw_inputs(f, columns[0], status);
+      draw_log(f, columns[1], log, scroll);
+      draw_prompt(f, rows[2], prompt);
+    }) {
+      Ok(_) => Ok(()),
+      Err(e) => Err(format!("Couldn't draw to the terminal: {}", e))
+    }
+  }
+}
+
+impl ClientView for TUIView {
+  fn update(&mut self) -> Result<(), String> {
+    loop {
+      match event::poll(time::Duration::from_secs(0)) {
+        Ok(true) => match event::read() {
+          Ok(Event::Key(key)) => self.handle_key(&key),
+          Ok(Event::Resize(_, _)) => self.dirty = true,
+          Ok(_) => (),
+          Err(e) => return Err(format!("Couldn't read from the terminal: {}", e))
+        },
+        Ok(false) => break,
+        Err(e) => return Err(format!("Couldn't read from the terminal: {}", e))
+      }
+    }
+    if self.dirty {
+      self.dirty = false;
+      return self.draw();
+    }
+    return Ok(());
+  }
+
+  fn write(&mut self, s: String) -> () {
+    self.push_log(s, false);
+  }
+
+  fn writeln(&mut self, s: String) -> () {
+    self.push_log(s, true);
+  }
+
+  // The status panel already shows this, so the log is left alone.
+  fn watch_status(&mut self, status: &ClientStatus) -> () {
+    self.update_status(status);
+  }
+
+  fn update_status(&mut self, status: &ClientStatus) -> () {
+    self.status = Some(status.clone());
+    self.dirty = true;
+  }
+
+  // Gives the terminal back in the state it was in before this view opened.
+  fn close(&mut self) -> () {
+    let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    let _ = self.terminal.show_cursor();
+  }
+
+  fn get_command_buffer(&mut self) -> Vec<String> {
+    let cloned: Vec<String> = self.command_buffer.clone();
+    self.command_buffer.clear();
+    return cloned;
+  }
+}
+
+// Draws a table with one row per slot.
+fn draw_slots<B: Backend>(
+  f: &mut Frame<B>, area: Rect, status: &Option<ClientStatus>
+) -> () {
+  let title: String = match status {
+    Some(status) if *status.get_running() => format!(
+      " {} - {} - {} packets sent, {} failed - {:.2} ms jitter - {} \
+      events/s - {} queued ",
+      if *status.get_reconnecting() { "Reconnecting" } else { "Running" },
+      status.get_server_ip(),
+      status.get_packets_sent(),
+      status.get_stats().get_packets_failed(),
+      status.get_stats().get_jitter_mean(),
+      status.get_stats().get_events_per_second(),
+      status.get_stats().get_queue_depth()
+    ),
+    Some(status) => match status.get_cleanup() {
+      Some(progress) => format!(
+        " Stopped - cleaning up gamepads ({:.0}% done) ", progress * 100.0
+      ),
+      None => " Stopped ".to_string()
+    },
+    None => " Starting ".to_string()
+  };
+  let mut rows: Vec<Row> = vec!();
+  if let Some(status) = status {
+    for (i, slot) in status.get_slots().iter().enumerate() {
+      let gamepad: String = match slot.get_gamepad() {
+        Some((gamepad_id, name)) => format!("{} (id: {})", name, gamepad_id),
+        None if *slot.get_held() => "held".to_string(),
+        None => "empty".to_string()
+      };
+      rows.push(
+        Row::new(
+          vec!(
+            Cell::from(format!("{}", i + 1)),
+            Cell::from(slot.get_nickname().clone().unwrap_or("".to_string())),
+            Cell::from(format!("{:?}", slot.get_switch_pad())),
+            Cell::from(format!("{}", slot.get_input_delay())),
+            Cell::from(if *slot.get_locked() { "yes" } else { "" }),
+            Cell::from(gamepad)
+          )
+        )
+      );
+    }
+  }
+  let table: Table = Table::new(rows)
+    .header(
+      Row::new(vec!("Slot", "Nickname", "Type", "Delay", "Locked", "Gamepad"))
+        .style(Style::default().add_modifier(Modifier::BOLD))
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .widths(
+      &[
+        Constraint::Length(4),
+        Constraint::Length(10),
+        Constraint::Length(14),
+        Constraint::Length(5),
+        Constraint::Length(6),
+        Constraint::Min(10)
+      ]
+    );
+  f.render_widget(table, area);
+}
+
+// Draws every connected slot's pressed buttons and stick positions.
+fn draw_inputs<B: Backend>(
+  f: &mut Frame<B>, area: Rect, status: &Option<ClientStatus>
+) -> () {
+  let mut lines: Vec<Spans> = vec!();
+  if let Some(status) = status {
+    for (i, slot) in status.get_slots().iter().enumerate() {
+      if *slot.get_switch_pad() == SwitchPad::Disconnected {
+        continue;
+      }
+      lines.push(
+        Spans::from(
+          Span::styled(
+            match slot.get_nickname() {
+              Some(nickname) => format!("Slot {} ({})", i + 1, nickname),
+              None => format!("Slot {}", i + 1)
+            },
+            Style::default().add_modifier(Modifier::BOLD)
+          )
+        )
+      );
+      let mut buttons: Vec<Span> = vec!();
+      for button in SwitchButton::all() {
+        if is_stick_direction(&button) {
+          continue;
+        }
+        let style: Style = if slot.get_keyout() & button.value() != 0 {
+          Style::default().fg(Color::Black).bg(Color::Green)
+        } else {
+          Style::default().fg(Color::DarkGray)
+        };
+        buttons.push(Span::styled(format!("{:?}", button), style));
+        buttons.push(Span::raw(" "));
+      }
+      lines.push(Spans::from(buttons));
+      lines.push(
+        Spans::from(
+          format!(
+            "L {} R {}",
+            to_stick_text(slot.get_left()),
+            to_stick_text(slot.get_right())
+          )
+        )
+      );
+    }
+  }
+  let paragraph: Paragraph = Paragraph::new(lines)
+    .block(Block::default().borders(Borders::ALL).title(" Inputs "))
+    .wrap(tui::widgets::Wrap { trim: false });
+  f.render_widget(paragraph, area);
+}
+
+/**
+ * Draws the most recent lines of the log that fit, offset by how far it's
+ * scrolled. The scroll is clamped here since it depends on the area's size.
+ */
+fn draw_log<B: Backend>(
+  f: &mut Frame<B>, area: Rect, log: &Vec<String>, scroll: &mut usize
+) -> () {
+  let width: usize = area.width.saturating_sub(2).max(1) as usize;
+  let height: usize = area.height.saturating_sub(2) as usize;
+  let mut wrapped: Vec<String> = vec!();
+  for line in log {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+      wrapped.push(String::new());
+    }
+    for chunk in chars.chunks(width) {
+      wrapped.push(chunk.iter().collect());
+    }
+  }
+  *scroll = (*scroll).min(wrapped.len().saturating_sub(height));
+  let end: usize = wrapped.len() - *scroll;
+  let start: usize = end.saturating_sub(height);
+  let lines: Vec<Spans> = wrapped[start..end]
+    .iter()
+    .map(|line| Spans::from(line.to_string()))
+    .collect();
+  let title: String = if *scroll > 0 {
+    format!(" Log (scrolled up {} lines) ", scroll)
+  } else {
+    " Log ".to_string()
+  };
+  f.render_widget(
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+    area
+  );
+}
+
+/**
+ * Draws the command prompt and puts the cursor at the end of it. A command too
+ * long for the box only has its end shown, so the cursor stays in the box.
+ */
+fn draw_prompt<B: Backend>(f: &mut Frame<B>, area: Rect, prompt: &str) -> () {
+  // The borders, the "> " and the cursor itself take up 5 columns.
+  let room: usize = area.width.saturating_sub(5) as usize;
+  let skipped: usize = prompt.chars().count().saturating_sub(room);
+  let shown: String = prompt.chars().skip(skipped).collect();
+  f.render_widget(
+    Paragraph::new(format!("> {}", shown)).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title(" Command (PgUp/PgDn to scroll, Esc to clear) ")
+    ),
+    area
+  );
+  f.set_cursor(
+    cmp::min(
+      area.x + 3 + shown.chars().count() as u16,
+      area.x + area.width.saturating_sub(2)
+    ),
+    area.y + 1
+  );
+}
+
+// Returns whether a Switch button is just a stick direction.
+fn is_stick_direction(button: &SwitchButton) -> bool {
+  return matches!(
+    button,
+    SwitchButton::LL | SwitchButton::LU | SwitchButton::LR | SwitchButton::LD
+      | SwitchButton::RL | SwitchButton::RU | SwitchButton::RR
+      | SwitchButton::RD
+  );
+}
+
+// Converts a stick's position to percentages, which are easier to read.
+fn to_stick_text(stick: &(i32, i32)) -> String {
+  return format!(
+    "({:>4}%, {:>4}%)",
+    stick.0 * 100 / 32767,
+    stick.1 * 100 / 32767
+  );
+}